@@ -0,0 +1,207 @@
+// core/src/analysis.rs
+
+use crate::limits::Budget;
+use crate::position::Position;
+use crate::search::Search;
+use crate::{Clock, ConnectFour, Coordinate, GameState, Outcome, SearchLimits};
+use serde::{Deserialize, Serialize};
+
+/// How many solver nodes `ConnectFour::analyze` may spend on each column.
+const SOLVER_NODES: u64 = 100_000;
+
+/// What the search found out about one legal move.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ColumnAnalysis {
+    pub column: Coordinate,
+    /// Heuristic minimax score for the player to move; higher is better.
+    pub score: i32,
+    /// The exact result of the move with perfect play, for the player to move,
+    /// when the solver could prove it within its budget.
+    pub outcome: Option<Outcome>,
+    /// The expected continuation, starting with `column`.
+    pub principal_variation: Vec<Coordinate>,
+}
+
+impl ConnectFour {
+    /// Scores every legal column of the position, in column order.
+    ///
+    /// Uses `std::time::Instant`; on wasm32 use `analyze_with_clock` with a clock
+    /// provided by the host.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn analyze(&self, state: &GameState, limits: SearchLimits) -> Vec<ColumnAnalysis> {
+        self.analyze_with_clock(state, limits, &crate::SystemClock::new())
+    }
+
+    /// Scores every legal column of the position, in column order.
+    ///
+    /// Scores and principal variations come from the deepest iteration of the
+    /// alpha-beta search that finished within the limits. Without
+    /// `limits.depth` the search goes as deep as a node or time limit allows,
+    /// or to `max_depth` when neither is set. Each column also gets a
+    /// bounded exact solve, so `outcome` is usually known late in the game.
+    /// Boards that do not fit in a `Position` are scored without variations or
+    /// outcomes beyond immediate wins.
+    pub fn analyze_with_clock(
+        &self,
+        state: &GameState,
+        limits: SearchLimits,
+        clock: &dyn Clock,
+    ) -> Vec<ColumnAnalysis> {
+        if state.is_game_over {
+            return Vec::new();
+        }
+        let depth = limits.max_depth(self.max_depth);
+        let Some(root) = Position::from_state(self, state) else {
+            return self.analyze_on_board(state, depth, &mut Budget::new(&limits, clock));
+        };
+
+        let scores = {
+            let mut table = self.transposition_table.borrow_mut();
+            Search::new(self, &mut table, &root)
+                .with_limits(&limits, clock)
+                .analyze(&root, depth)
+        };
+        let mut solver = self.solver.borrow_mut();
+        scores
+            .into_iter()
+            .map(|(column, score, principal_variation)| {
+                let mut child = root;
+                child.play(column);
+                let outcome =
+                    solver
+                        .solve_within(&child, SOLVER_NODES)
+                        .map(|outcome| match outcome {
+                            Outcome::Win { moves } => Outcome::Loss { moves: moves + 1 },
+                            Outcome::Loss { moves } => Outcome::Win { moves: moves + 1 },
+                            Outcome::Draw => Outcome::Draw,
+                        });
+                ColumnAnalysis {
+                    column,
+                    score,
+                    outcome,
+                    principal_variation,
+                }
+            })
+            .collect()
+    }
+
+    /// Board-based fallback for `analyze` on games a `Position` cannot hold,
+    /// deepening one ply at a time until `max_depth` or the end of the budget.
+    fn analyze_on_board(
+        &self,
+        state: &GameState,
+        max_depth: usize,
+        budget: &mut Budget,
+    ) -> Vec<ColumnAnalysis> {
+        let max_depth = max_depth.min(self.empty_cells(&state.board).saturating_sub(1));
+
+        let mut analysis = Vec::new();
+        for depth in 0..=max_depth {
+            let mut scores = Vec::new();
+            for column in self.get_valid_columns(&state.board) {
+                let new_state = self.place_piece(state, column);
+                let score = self.minimax(
+                    &new_state,
+                    depth,
+                    i32::MIN,
+                    i32::MAX,
+                    state.current_player,
+                    budget,
+                );
+                if budget.aborted() {
+                    return analysis;
+                }
+                // In misère completing a line loses
+                let outcome = match new_state.winner {
+                    Some(winner) if winner == state.current_player => {
+                        Some(Outcome::Win { moves: 1 })
+                    }
+                    Some(_) => Some(Outcome::Loss { moves: 1 }),
+                    None if new_state.is_game_over => Some(Outcome::Draw),
+                    None => None,
+                };
+                scores.push(ColumnAnalysis {
+                    column,
+                    score,
+                    outcome,
+                    principal_variation: vec![column],
+                });
+            }
+            analysis = scores;
+            budget.allow_abort();
+        }
+        analysis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConnectFourConfig;
+
+    #[test]
+    fn test_scores_match_best_move() {
+        let game = ConnectFourConfig::new().max_depth(4).build().unwrap();
+        let state = game.state_from_moves("4435").unwrap();
+        let analysis = game.analyze(&state, SearchLimits::default());
+        assert_eq!(analysis.len(), 7);
+        let best = analysis.iter().map(|a| a.score).max().unwrap();
+        let col = game.get_best_move_on_board(&state);
+        assert_eq!(analysis[col].score, best);
+        for column in &analysis {
+            assert_eq!(column.principal_variation[0], column.column);
+            assert!(column.principal_variation.len() <= 5);
+        }
+    }
+
+    #[test]
+    fn test_budget_searches_past_max_depth() {
+        let game = ConnectFourConfig::new().max_depth(1).build().unwrap();
+        let state = game.state_from_moves("43443534536656").unwrap();
+        let analysis = game.analyze(&state, SearchLimits::nodes(5_000));
+        assert!(analysis.iter().any(|a| a.principal_variation.len() > 2));
+    }
+
+    #[test]
+    fn test_solved_outcomes() {
+        let game = ConnectFour::new();
+        // Red threatens both ends of an open three, so every move loses
+        let state = game.state_from_moves("44553").unwrap();
+        let analysis = game.analyze(&state, SearchLimits::depth(2));
+        for column in &analysis {
+            assert_eq!(column.outcome, Some(Outcome::Loss { moves: 2 }));
+        }
+
+        let state = game.state_from_moves("121212").unwrap();
+        let analysis = game.analyze(&state, SearchLimits::depth(1));
+        assert_eq!(analysis[0].outcome, Some(Outcome::Win { moves: 1 }));
+        assert_eq!(analysis[0].principal_variation, vec![0]);
+    }
+
+    #[test]
+    fn test_large_board() {
+        let game = ConnectFourConfig::new()
+            .num_columns(10)
+            .num_rows(8)
+            .max_depth(2)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("1").unwrap();
+        let analysis = game.analyze(&state, SearchLimits::default());
+        assert_eq!(analysis.len(), 10);
+        assert!(analysis.iter().all(|a| a.outcome.is_none()));
+    }
+
+    #[test]
+    fn test_misere_line_is_a_loss() {
+        let game = ConnectFourConfig::new()
+            .variant(crate::Variant::Misere)
+            .max_depth(2)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("121212").unwrap();
+        let analysis = game.analyze(&state, SearchLimits::default());
+        assert_eq!(analysis[0].outcome, Some(Outcome::Loss { moves: 1 }));
+        assert!(analysis[1..].iter().all(|a| a.outcome.is_none()));
+    }
+}
@@ -0,0 +1,98 @@
+// core/src/bin/perft.rs
+
+//! Counts the game tree below a position, one line per depth.
+//!
+//! Usage: `perft [options] <depth>`, with options
+//! `--columns <n>`, `--rows <n>`, `--length <n>`, `--players <n>`,
+//! `--variant <standard|popout|popten|misere|mnk>`,
+//! `--topology <flat|cylinder>` and `--moves <sequence>` to start from a
+//! position other than the empty board.
+
+use connect_four_core::{ConnectFourConfig, Topology, Variant};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: perft [--columns N] [--rows N] [--length N] [--players N] \
+[--variant standard|popout|popten|misere|mnk] [--topology flat|cylinder] [--moves SEQUENCE] DEPTH";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("perft: {}", message);
+            eprintln!("{}", USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut config = ConnectFourConfig::new();
+    let mut moves = String::new();
+    let mut depth = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        config = match arg.as_str() {
+            "--columns" => config.num_columns(parse_number(&value()?)?),
+            "--rows" => config.num_rows(parse_number(&value()?)?),
+            "--length" => config.winning_length(parse_number(&value()?)?),
+            "--players" => config.num_players(parse_number(&value()?)?),
+            "--variant" => config.variant(parse_variant(&value()?)?),
+            "--topology" => config.topology(parse_topology(&value()?)?),
+            "--moves" => {
+                moves = value()?;
+                config
+            }
+            _ if depth.is_none() && !arg.starts_with("--") => {
+                depth = Some(parse_number(&arg)?);
+                config
+            }
+            _ => return Err(format!("unexpected argument {:?}", arg)),
+        };
+    }
+    let depth = depth.ok_or("missing depth")?;
+
+    let game = config.build().map_err(|e| e.to_string())?;
+    let state = game.state_from_moves(&moves).map_err(|e| e.to_string())?;
+
+    println!(
+        "{:>5} {:>14} {:>14} {:>14} {:>14}",
+        "depth", "nodes", "positions", "wins", "draws"
+    );
+    for (ply, counts) in game.perft(&state, depth).iter().enumerate() {
+        println!(
+            "{:>5} {:>14} {:>14} {:>14} {:>14}",
+            ply + 1,
+            counts.nodes,
+            counts.positions,
+            counts.wins,
+            counts.draws
+        );
+    }
+    Ok(())
+}
+
+fn parse_number(text: &str) -> Result<usize, String> {
+    text.parse()
+        .map_err(|_| format!("{:?} is not a number", text))
+}
+
+fn parse_variant(text: &str) -> Result<Variant, String> {
+    match text {
+        "standard" => Ok(Variant::Standard),
+        "popout" => Ok(Variant::PopOut),
+        "popten" => Ok(Variant::PopTen),
+        "misere" => Ok(Variant::Misere),
+        "mnk" => Ok(Variant::Mnk),
+        _ => Err(format!("unknown variant {:?}", text)),
+    }
+}
+
+fn parse_topology(text: &str) -> Result<Topology, String> {
+    match text {
+        "flat" => Ok(Topology::Flat),
+        "cylinder" => Ok(Topology::Cylinder),
+        _ => Err(format!("unknown topology {:?}", text)),
+    }
+}
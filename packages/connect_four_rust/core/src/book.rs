@@ -0,0 +1,433 @@
+// core/src/book.rs
+
+use crate::position::Position;
+use crate::{Cell, Color, ConnectFour, Coordinate, GameState, Quality, Topology};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"C4BK";
+const VERSION: u8 = 2;
+/// Books of this version are keyed by `Position::key` and converted on load.
+const VERSION_POSITION_KEYS: u8 = 1;
+const HEADER_LEN: usize = 13;
+const ENTRY_LEN: usize = 9;
+
+/// Errors produced while loading an opening book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookError {
+    /// The data does not start with the book magic bytes.
+    InvalidMagic,
+    /// The book was written by an unknown format version.
+    UnsupportedVersion(u8),
+    /// The data ends before the number of entries in the header.
+    Truncated,
+    /// The board size in the header does not fit in a `Position`.
+    InvalidDimensions,
+    /// The legacy JSON could not be parsed.
+    Json(String),
+    /// A legacy key does not describe a valid board.
+    InvalidLegacyKey(String),
+    /// An entry of a version 1 book does not describe a position and one of
+    /// its columns.
+    InvalidEntry(u64),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::InvalidMagic => write!(f, "not an opening book"),
+            BookError::UnsupportedVersion(version) => {
+                write!(f, "unsupported opening book version {}", version)
+            }
+            BookError::Truncated => write!(f, "opening book data is truncated"),
+            BookError::InvalidDimensions => write!(f, "opening book has invalid board dimensions"),
+            BookError::Json(e) => write!(f, "invalid legacy cache JSON: {}", e),
+            BookError::InvalidLegacyKey(key) => write!(f, "invalid legacy cache key: {}", key),
+            BookError::InvalidEntry(key) => write!(f, "invalid opening book entry {:#x}", key),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// Precomputed best moves for positions near the start of the game.
+///
+/// Positions are keyed by `Position::canonical_key`, which is exact for a given
+/// board size up to mirroring, so a position and its mirror image share one
+/// entry. Each entry's move is for the canonical form and is mirrored back on
+/// lookup. Version 1 books, keyed by `Position::key`, are converted on load.
+/// The binary format is a 13-byte header (`C4BK`, version, width, height, winning
+/// length, depth and a little-endian `u32` entry count) followed by the entries
+/// sorted by key, each a little-endian `u64` key and a one-byte column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpeningBook {
+    width: u8,
+    height: u8,
+    winning_length: u8,
+    depth: u8,
+    entries: HashMap<u64, u8>,
+}
+
+impl OpeningBook {
+    /// Creates an empty book for a board size.
+    pub fn new(game: &ConnectFour) -> Self {
+        OpeningBook {
+            width: game.num_columns as u8,
+            height: game.num_rows as u8,
+            winning_length: game.winning_length as u8,
+            depth: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Builds a book covering every position with fewer than `plies` stones.
+    ///
+    /// Each position's move is the one `game` picks at the given quality, so
+    /// generating with `Quality::Perfect` beyond a few plies takes a long time.
+    pub fn generate(game: &ConnectFour, plies: usize, quality: Quality) -> Self {
+        let mut book = Self::new(game);
+        book.depth = plies.min(u8::MAX as usize) as u8;
+        let Some(root) = Position::new(game.num_columns, game.num_rows, game.winning_length) else {
+            return book;
+        };
+
+        let mut seen = HashSet::new();
+        let mut frontier = vec![root];
+        while let Some(position) = frontier.pop() {
+            if position.moves() >= plies
+                || position.is_game_over()
+                || !seen.insert(position.canonical_key())
+            {
+                continue;
+            }
+            let col = game
+                .get_computer_move(&position.to_state(), quality)
+                .column();
+            book.insert(&position, col);
+            for col in 0..position.width() {
+                if position.can_play(col) {
+                    let mut child = position;
+                    child.play(col);
+                    frontier.push(child);
+                }
+            }
+        }
+        book
+    }
+
+    /// Imports the JSON state cache written by the TypeScript implementation.
+    ///
+    /// Keys list the columns separated by `|`, each column's cells from top to
+    /// bottom as `0`, `red` or `yellow`. Values are the best column.
+    pub fn from_legacy_json(game: &ConnectFour, json: &str) -> Result<Self, BookError> {
+        // Sorted, so that when the cache holds both a position and its mirror
+        // image the same one's move is kept every time
+        let cache: BTreeMap<String, usize> =
+            serde_json::from_str(json).map_err(|e| BookError::Json(e.to_string()))?;
+
+        let mut book = Self::new(game);
+        for (key, col) in cache {
+            let invalid = || BookError::InvalidLegacyKey(key.clone());
+            let state = parse_legacy_key(game, &key).ok_or_else(invalid)?;
+            let position = Position::from_state(game, &state).ok_or_else(invalid)?;
+            if col >= game.num_columns {
+                return Err(invalid());
+            }
+            book.insert(&position, col);
+        }
+        Ok(book)
+    }
+
+    /// Parses a book from its binary format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(if bytes.starts_with(MAGIC) || MAGIC.starts_with(bytes) {
+                BookError::Truncated
+            } else {
+                BookError::InvalidMagic
+            });
+        }
+        if &bytes[..4] != MAGIC {
+            return Err(BookError::InvalidMagic);
+        }
+        let version = bytes[4];
+        if version != VERSION && version != VERSION_POSITION_KEYS {
+            return Err(BookError::UnsupportedVersion(version));
+        }
+        let (width, height, winning_length, depth) = (bytes[5], bytes[6], bytes[7], bytes[8]);
+        if !Position::fits(width as usize, height as usize) {
+            return Err(BookError::InvalidDimensions);
+        }
+        let count = u32::from_le_bytes([bytes[9], bytes[10], bytes[11], bytes[12]]) as usize;
+        let body = &bytes[HEADER_LEN..];
+        if count
+            .checked_mul(ENTRY_LEN)
+            .is_none_or(|len| body.len() < len)
+        {
+            return Err(BookError::Truncated);
+        }
+
+        let entries = body
+            .chunks_exact(ENTRY_LEN)
+            .take(count)
+            .map(|entry| {
+                let mut key = [0; 8];
+                key.copy_from_slice(&entry[..8]);
+                (u64::from_le_bytes(key), entry[8])
+            })
+            .collect();
+        let book = OpeningBook {
+            width,
+            height,
+            winning_length,
+            depth,
+            entries,
+        };
+        if version == VERSION_POSITION_KEYS {
+            return book.with_canonical_keys();
+        }
+        Ok(book)
+    }
+
+    /// Rekeys the entries of a version 1 book by `Position::canonical_key`.
+    fn with_canonical_keys(self) -> Result<Self, BookError> {
+        let mut book = OpeningBook {
+            entries: HashMap::new(),
+            ..self
+        };
+        // Sorted, so that when the book holds both a position and its mirror
+        // image the same one's move is kept every time
+        let mut entries: Vec<(u64, u8)> = self.entries.into_iter().collect();
+        entries.sort_unstable();
+        for (key, col) in entries {
+            let position = Position::from_key(
+                self.width as usize,
+                self.height as usize,
+                self.winning_length as usize,
+                key,
+            )
+            .filter(|_| col < self.width)
+            .ok_or(BookError::InvalidEntry(key))?;
+            book.insert(&position, col as Coordinate);
+        }
+        Ok(book)
+    }
+
+    /// Serializes the book into its binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<(&u64, &u8)> = self.entries.iter().collect();
+        entries.sort_unstable();
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + entries.len() * ENTRY_LEN);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[
+            VERSION,
+            self.width,
+            self.height,
+            self.winning_length,
+            self.depth,
+        ]);
+        bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for (key, col) in entries {
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.push(*col);
+        }
+        bytes
+    }
+
+    /// Returns true if the book was built for the game's board size. Books are
+    /// for flat boards only.
+    pub fn matches(&self, game: &ConnectFour) -> bool {
+        game.topology == Topology::Flat
+            && self.width as usize == game.num_columns
+            && self.height as usize == game.num_rows
+            && self.winning_length as usize == game.winning_length
+    }
+
+    /// Number of plies from the start that the book covers.
+    pub fn depth(&self) -> usize {
+        self.depth as usize
+    }
+
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the book has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Records the move to play in a position, replacing any move recorded for
+    /// it or its mirror image.
+    pub fn insert(&mut self, position: &Position, col: Coordinate) {
+        self.depth = self.depth.max(position.moves() as u8 + 1);
+        let (canonical, mirrored) = position.canonical();
+        let col = if mirrored {
+            position.mirror_column(col)
+        } else {
+            col
+        };
+        self.entries.insert(canonical.key(), col as u8);
+    }
+
+    /// Returns the book move for a position, if it is playable. Positions whose
+    /// mirror image is in the book get its move mirrored.
+    pub fn get(&self, position: &Position) -> Option<Coordinate> {
+        let (canonical, mirrored) = position.canonical();
+        self.entries
+            .get(&canonical.key())
+            .map(|&col| col as Coordinate)
+            .filter(|&col| col < position.width())
+            .map(|col| {
+                if mirrored {
+                    position.mirror_column(col)
+                } else {
+                    col
+                }
+            })
+            .filter(|&col| position.can_play(col))
+    }
+}
+
+/// Decodes a `stateCache.json` key into a game state, inferring the side to move.
+fn parse_legacy_key(game: &ConnectFour, key: &str) -> Option<GameState> {
+    let mut board = game.create_board();
+    let columns: Vec<&str> = key.split('|').collect();
+    if columns.len() != game.num_columns {
+        return None;
+    }
+
+    let (mut red, mut yellow) = (0i32, 0i32);
+    for (column, mut rest) in board.iter_mut().zip(columns) {
+        for cell in column.iter_mut() {
+            let (value, len) = if rest.starts_with("red") {
+                red += 1;
+                (Cell::Filled(Color::Red), 3)
+            } else if rest.starts_with("yellow") {
+                yellow += 1;
+                (Cell::Filled(Color::Yellow), 6)
+            } else if rest.starts_with('0') {
+                (Cell::Empty, 1)
+            } else {
+                return None;
+            };
+            *cell = value;
+            rest = &rest[len..];
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+    }
+
+    let current_player = match red - yellow {
+        0 => Color::Red,
+        1 => Color::Yellow,
+        _ => return None,
+    };
+    Some(GameState {
+        board,
+        current_player,
+        winner: None,
+        is_game_over: false,
+        moves: Vec::new(),
+        undone_moves: Vec::new(),
+        pop_ten: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_round_trip() {
+        let game = ConnectFour::new();
+        let book = OpeningBook::generate(&game, 2, Quality::Best);
+        // The empty board and four first moves, the other three being mirrored
+        assert_eq!(book.len(), 5);
+        assert_eq!(book.depth(), 2);
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), 13 + 5 * 9);
+        assert_eq!(OpeningBook::from_bytes(&bytes), Ok(book));
+    }
+
+    #[test]
+    fn test_rejects_bad_data() {
+        assert_eq!(
+            OpeningBook::from_bytes(b"nope, not a book"),
+            Err(BookError::InvalidMagic)
+        );
+        let game = ConnectFour::new();
+        let mut bytes = OpeningBook::generate(&game, 1, Quality::Best).to_bytes();
+        bytes.pop();
+        assert_eq!(OpeningBook::from_bytes(&bytes), Err(BookError::Truncated));
+        // A huge entry count must not overflow on 32-bit targets
+        let mut huge = bytes.clone();
+        huge[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(OpeningBook::from_bytes(&huge), Err(BookError::Truncated));
+        bytes[4] = 9;
+        assert_eq!(
+            OpeningBook::from_bytes(&bytes),
+            Err(BookError::UnsupportedVersion(9))
+        );
+    }
+
+    #[test]
+    fn test_version_one_keys_are_converted() {
+        let game = ConnectFour::new();
+        let position = Position::from_state(&game, &game.state_from_moves("1").unwrap()).unwrap();
+        let mut bytes = vec![b'C', b'4', b'B', b'K', 1, 7, 6, 4, 2];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&position.key().to_le_bytes());
+        bytes.push(1);
+
+        let book = OpeningBook::from_bytes(&bytes).unwrap();
+        assert_eq!(book.get(&position), Some(1));
+        assert_eq!(book.get(&position.mirrored()), Some(5));
+        assert_eq!(book.depth(), 2);
+
+        // Stones above the top of a column are not a position
+        bytes[13..21].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            OpeningBook::from_bytes(&bytes),
+            Err(BookError::InvalidEntry(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_legacy_import() {
+        let game = ConnectFour::new();
+        let json = r#"{
+            "000000|000000|000000|00000red|000000|000000|000000": 2,
+            "000000|000000|000000|0000yellowred|000000|00000red|000000": 4
+        }"#;
+        let book = OpeningBook::from_legacy_json(&game, json).unwrap();
+        assert_eq!(book.len(), 2);
+
+        let mut state = game.create_initial_state();
+        state = game.place_piece(&state, 3);
+        let position = Position::from_state(&game, &state).unwrap();
+        assert_eq!(book.get(&position), Some(2));
+
+        // The mirror image of the second entry gets the mirrored move
+        let state = game.state_from_moves("442").unwrap();
+        let position = Position::from_state(&game, &state).unwrap();
+        assert_eq!(book.get(&position), Some(2));
+
+        assert!(matches!(
+            OpeningBook::from_legacy_json(&game, r#"{"red|0": 1}"#),
+            Err(BookError::InvalidLegacyKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_shipped_legacy_cache() {
+        let game = ConnectFour::new();
+        let json = include_str!("../../../utils/src/stateCache.json");
+        let book = OpeningBook::from_legacy_json(&game, json).unwrap();
+        // Mirror images share an entry, which roughly halves the 245 positions
+        assert_eq!(book.len(), 125);
+    }
+}
@@ -0,0 +1,311 @@
+// core/src/config.rs
+
+use crate::position::Position;
+use crate::random;
+use crate::{
+    ConnectFour, EvaluationWeights, Solver, Topology, TranspositionTable, Variant,
+    WeightedEvaluator, MAX_PLAYERS,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+
+/// Largest supported number of columns or rows. Columns are stored in a byte
+/// by the transposition table and the opening book.
+pub const MAX_DIMENSION: usize = 64;
+
+/// Why a `ConnectFourConfig` was rejected.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The board needs at least one column and one row.
+    EmptyBoard,
+    /// A dimension is larger than `MAX_DIMENSION`.
+    BoardTooLarge { num_columns: usize, num_rows: usize },
+    /// Lines must be at least two cells long.
+    WinningLengthTooShort { winning_length: usize },
+    /// No line of this length fits on the board.
+    WinningLengthTooLong {
+        winning_length: usize,
+        num_columns: usize,
+        num_rows: usize,
+    },
+    /// Games need two to `MAX_PLAYERS` players, and the pop and misère variants
+    /// exactly two.
+    UnsupportedPlayerCount {
+        num_players: usize,
+        variant: Variant,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::EmptyBoard => write!(f, "the board needs at least one column and row"),
+            ConfigError::BoardTooLarge {
+                num_columns,
+                num_rows,
+            } => write!(
+                f,
+                "a {}x{} board is too large (at most {} columns and rows)",
+                num_columns, num_rows, MAX_DIMENSION
+            ),
+            ConfigError::WinningLengthTooShort { winning_length } => {
+                write!(f, "winning length {} is too short", winning_length)
+            }
+            ConfigError::WinningLengthTooLong {
+                winning_length,
+                num_columns,
+                num_rows,
+            } => write!(
+                f,
+                "winning length {} does not fit on a {}x{} board",
+                winning_length, num_columns, num_rows
+            ),
+            ConfigError::UnsupportedPlayerCount {
+                num_players,
+                variant,
+            } => write!(
+                f,
+                "{:?} cannot be played by {} players",
+                variant, num_players
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Builder for a `ConnectFour` with a custom board size, line length or search
+/// depth. The defaults are the standard 7x6 connect-four game.
+///
+/// Configs can be loaded with serde; missing fields keep their defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ConnectFourConfig {
+    num_columns: usize,
+    num_rows: usize,
+    winning_length: usize,
+    max_depth: usize,
+    num_players: usize,
+    variant: Variant,
+    topology: Topology,
+    table_memory: usize,
+    weights: EvaluationWeights,
+    seed: Option<u64>,
+}
+
+impl ConnectFourConfig {
+    /// Creates a config with the standard settings.
+    pub fn new() -> Self {
+        ConnectFourConfig {
+            num_columns: 7,
+            num_rows: 6,
+            winning_length: 4,
+            max_depth: 5, // Adjusted depth for performance
+            num_players: 2,
+            variant: Variant::Standard,
+            topology: Topology::Flat,
+            table_memory: TranspositionTable::DEFAULT_MEMORY,
+            weights: EvaluationWeights::default(),
+            seed: None,
+        }
+    }
+
+    /// Sets the number of columns.
+    pub fn num_columns(mut self, num_columns: usize) -> Self {
+        self.num_columns = num_columns;
+        self
+    }
+
+    /// Sets the number of rows.
+    pub fn num_rows(mut self, num_rows: usize) -> Self {
+        self.num_rows = num_rows;
+        self
+    }
+
+    /// Sets how many pieces in a line win the game.
+    pub fn winning_length(mut self, winning_length: usize) -> Self {
+        self.winning_length = winning_length;
+        self
+    }
+
+    /// Sets the search depth used by `Quality::Best`.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets how many players take turns, from two to `MAX_PLAYERS`.
+    pub fn num_players(mut self, num_players: usize) -> Self {
+        self.num_players = num_players;
+        self
+    }
+
+    /// Sets the rules the game is played by.
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Sets how the edges of the board connect.
+    pub fn topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Sets the memory budget of each transposition table, in bytes.
+    pub fn table_memory(mut self, bytes: usize) -> Self {
+        self.table_memory = bytes;
+        self
+    }
+
+    /// Sets the weights of the default evaluator.
+    pub fn evaluation_weights(mut self, weights: EvaluationWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Seeds the random choices of `Quality::Bad`, `Quality::Medium` and
+    /// `Quality::Mcts`, which are otherwise seeded from the operating system.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Checks the settings without building a game.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.num_columns == 0 || self.num_rows == 0 {
+            return Err(ConfigError::EmptyBoard);
+        }
+        if self.num_columns > MAX_DIMENSION || self.num_rows > MAX_DIMENSION {
+            return Err(ConfigError::BoardTooLarge {
+                num_columns: self.num_columns,
+                num_rows: self.num_rows,
+            });
+        }
+        if self.winning_length < 2 {
+            return Err(ConfigError::WinningLengthTooShort {
+                winning_length: self.winning_length,
+            });
+        }
+        if self.winning_length > self.num_columns.max(self.num_rows) {
+            return Err(ConfigError::WinningLengthTooLong {
+                winning_length: self.winning_length,
+                num_columns: self.num_columns,
+                num_rows: self.num_rows,
+            });
+        }
+        let max_players = match self.variant {
+            Variant::Standard | Variant::Mnk => MAX_PLAYERS,
+            Variant::PopOut | Variant::PopTen | Variant::Misere => 2,
+        };
+        if !(2..=max_players).contains(&self.num_players) {
+            return Err(ConfigError::UnsupportedPlayerCount {
+                num_players: self.num_players,
+                variant: self.variant,
+            });
+        }
+        Ok(())
+    }
+
+    /// Validates the settings and builds the game.
+    pub fn build(self) -> Result<ConnectFour, ConfigError> {
+        self.validate()?;
+        let lines = self
+            .topology
+            .lines(self.num_columns, self.num_rows, self.winning_length);
+        let windows = Position::new(self.num_columns, self.num_rows, self.winning_length)
+            .map(|position| lines.iter().map(|line| position.cells_mask(line)).collect())
+            .unwrap_or_default();
+        Ok(ConnectFour {
+            num_columns: self.num_columns,
+            num_rows: self.num_rows,
+            winning_length: self.winning_length,
+            max_depth: self.max_depth,
+            num_players: self.num_players,
+            variant: self.variant,
+            topology: self.topology,
+            transposition_table: RefCell::new(TranspositionTable::with_memory(self.table_memory)),
+            solver: RefCell::new(Solver::with_memory(self.table_memory)),
+            opening_book: None,
+            evaluator: Box::new(WeightedEvaluator::new(self.weights)),
+            rng: RefCell::new(self.seed.map_or_else(random::from_entropy, random::seeded)),
+            lines,
+            windows,
+        })
+    }
+}
+
+impl Default for ConnectFourConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_standard() {
+        let game = ConnectFourConfig::new().build().unwrap();
+        assert_eq!(game.num_columns(), 7);
+        assert_eq!(game.num_rows(), 6);
+        assert_eq!(game.winning_length(), 4);
+        assert_eq!(game.max_depth(), 5);
+    }
+
+    #[test]
+    fn test_rejects_invalid_geometry() {
+        assert_eq!(
+            ConnectFourConfig::new().num_rows(0).build().err(),
+            Some(ConfigError::EmptyBoard)
+        );
+        assert_eq!(
+            ConnectFourConfig::new().winning_length(1).build().err(),
+            Some(ConfigError::WinningLengthTooShort { winning_length: 1 })
+        );
+        assert_eq!(
+            ConnectFourConfig::new().winning_length(8).build().err(),
+            Some(ConfigError::WinningLengthTooLong {
+                winning_length: 8,
+                num_columns: 7,
+                num_rows: 6
+            })
+        );
+        assert!(matches!(
+            ConnectFourConfig::new().num_columns(65).build(),
+            Err(ConfigError::BoardTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unsupported_player_counts() {
+        assert_eq!(
+            ConnectFourConfig::new().num_players(1).build().err(),
+            Some(ConfigError::UnsupportedPlayerCount {
+                num_players: 1,
+                variant: Variant::Standard
+            })
+        );
+        assert!(ConnectFourConfig::new()
+            .num_players(MAX_PLAYERS)
+            .build()
+            .is_ok());
+        assert!(ConnectFourConfig::new()
+            .num_players(MAX_PLAYERS + 1)
+            .build()
+            .is_err());
+        assert_eq!(
+            ConnectFourConfig::new()
+                .num_players(3)
+                .variant(Variant::PopOut)
+                .build()
+                .err(),
+            Some(ConfigError::UnsupportedPlayerCount {
+                num_players: 3,
+                variant: Variant::PopOut
+            })
+        );
+    }
+}
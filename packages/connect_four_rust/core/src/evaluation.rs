@@ -0,0 +1,269 @@
+// core/src/evaluation.rs
+
+use crate::position::Position;
+use crate::{Board, Cell, Color, ConnectFour, Variant};
+use serde::{Deserialize, Serialize};
+
+/// Scores positions at the leaves of the alpha-beta search.
+///
+/// Scores are from `player`'s point of view, higher is better, and must be the
+/// same whichever method computes them. Set a game's evaluator with
+/// `ConnectFour::set_evaluator`.
+pub trait Evaluator {
+    /// Scores a board.
+    fn evaluate(&self, game: &ConnectFour, board: &Board, player: Color) -> i32;
+
+    /// Scores a bitboard position. The default converts it to a board; override
+    /// it when the evaluator can work on bitboards directly.
+    fn evaluate_position(&self, game: &ConnectFour, position: &Position, player: Color) -> i32 {
+        self.evaluate(game, &position.to_state().board, player)
+    }
+
+    /// Returns true if mirroring a board of `game` left to right never changes
+    /// its score. The search then lets a position and its mirror image share a
+    /// transposition table entry. Defaults to false.
+    fn is_symmetric(&self, _game: &ConnectFour) -> bool {
+        false
+    }
+}
+
+/// The weights of `WeightedEvaluator`. Opponent weights are subtracted.
+///
+/// Every field has a default, so a config only needs the weights it changes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct EvaluationWeights {
+    /// Bonus for each of the player's pieces in the center column.
+    pub center: i32,
+    /// A complete line of the player's pieces.
+    pub win: i32,
+    /// A line one piece short of complete, with the last cell empty.
+    pub one_short: i32,
+    /// A line two pieces short of complete, with both cells empty.
+    pub two_short: i32,
+    /// A complete line of the opponent's pieces.
+    pub opponent_win: i32,
+    /// An opponent line one piece short of complete, with the last cell empty.
+    pub opponent_one_short: i32,
+    /// An opponent line two pieces short of complete, with both cells empty.
+    pub opponent_two_short: i32,
+}
+
+impl Default for EvaluationWeights {
+    fn default() -> Self {
+        EvaluationWeights {
+            center: 6,
+            win: 100000,
+            one_short: 100,
+            two_short: 10,
+            opponent_win: 100000,
+            opponent_one_short: 1000,
+            opponent_two_short: 10,
+        }
+    }
+}
+
+/// The default evaluator: a center-column bonus plus a score for every window of
+/// `winning_length` cells, depending on how close each player is to filling it.
+/// Windows follow the game's `Topology`. In `Variant::Misere` the score is
+/// negated, since there the lines a player builds are the ones they lose with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WeightedEvaluator {
+    weights: EvaluationWeights,
+}
+
+impl WeightedEvaluator {
+    pub fn new(weights: EvaluationWeights) -> Self {
+        WeightedEvaluator { weights }
+    }
+
+    pub fn weights(&self) -> &EvaluationWeights {
+        &self.weights
+    }
+
+    /// Scores a window from the number of player, opponent and empty cells in it.
+    fn score_window(
+        &self,
+        length: usize,
+        player_count: usize,
+        opponent_count: usize,
+        empty_count: usize,
+    ) -> i32 {
+        let weights = &self.weights;
+        let mut score = 0;
+
+        if player_count == length {
+            score += weights.win; // Winning move
+        } else if player_count + 1 == length && empty_count == 1 {
+            score += weights.one_short; // One short of a line with an open spot
+        } else if player_count + 2 == length && empty_count == 2 {
+            score += weights.two_short; // Two short of a line with two open spots
+        }
+
+        if opponent_count == length {
+            score -= weights.opponent_win; // Opponent's winning move
+        } else if opponent_count + 1 == length && empty_count == 1 {
+            score -= weights.opponent_one_short; // Block opponent's line that is one short
+        } else if opponent_count + 2 == length && empty_count == 2 {
+            score -= weights.opponent_two_short; // Block opponent's line that is two short
+        }
+
+        score
+    }
+
+    /// Evaluates a window of cells and returns a score.
+    fn evaluate_window(&self, game: &ConnectFour, window_cells: &[Cell], player: Color) -> i32 {
+        let count = |wanted: Cell| window_cells.iter().filter(|&&cell| cell == wanted).count();
+        let player_count = count(Cell::Filled(player));
+        let empty_count = count(Cell::Empty);
+        // With more than two players only a window held by a single opponent is
+        // a threat; a mixed one cannot be completed by anyone
+        let opponent = window_cells
+            .iter()
+            .find(|&&cell| cell != Cell::Empty && cell != Cell::Filled(player));
+        let opponent_count = match opponent {
+            Some(&cell) if player_count + count(cell) + empty_count == window_cells.len() => {
+                count(cell)
+            }
+            _ => 0,
+        };
+
+        self.score_window(
+            game.winning_length,
+            player_count,
+            opponent_count,
+            empty_count,
+        )
+    }
+}
+
+impl Evaluator for WeightedEvaluator {
+    fn evaluate(&self, game: &ConnectFour, board: &Board, player: Color) -> i32 {
+        let mut score = 0;
+
+        // Score center column
+        let center_col = game.num_columns / 2;
+        let center_count = board[center_col]
+            .iter()
+            .filter(|&&cell| cell == Cell::Filled(player))
+            .count();
+        score += (center_count as i32) * self.weights.center;
+
+        // Score every line the topology allows, in all directions
+        let mut window_cells = Vec::with_capacity(game.winning_length);
+        for line in game.lines() {
+            window_cells.clear();
+            window_cells.extend(line.iter().map(|&(col, row)| board[col][row]));
+            score += self.evaluate_window(game, &window_cells, player);
+        }
+
+        for_variant(game, score)
+    }
+
+    fn evaluate_position(&self, game: &ConnectFour, position: &Position, player: Color) -> i32 {
+        let stones = position.stones(player);
+        let opponent = position.mask() ^ stones;
+        let length = position.winning_length();
+        let center = position.column_mask(position.width() / 2);
+
+        let mut score = ((stones & center).count_ones() as i32) * self.weights.center;
+        for &window in game.windows() {
+            let player_count = (stones & window).count_ones() as usize;
+            let opponent_count = (opponent & window).count_ones() as usize;
+            score += self.score_window(
+                length,
+                player_count,
+                opponent_count,
+                length - player_count - opponent_count,
+            );
+        }
+        for_variant(game, score)
+    }
+
+    /// The center bonus goes to column `width / 2`, which is only its own mirror
+    /// image on boards of odd width.
+    fn is_symmetric(&self, game: &ConnectFour) -> bool {
+        game.num_columns % 2 == 1
+    }
+}
+
+/// Turns a score of the standard rules into one for the game's variant.
+fn for_variant(game: &ConnectFour, score: i32) -> i32 {
+    if game.variant() == Variant::Misere {
+        -score
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectFourConfig, GameState};
+
+    /// Only counts pieces in the center column.
+    struct CenterOnly;
+
+    impl Evaluator for CenterOnly {
+        fn evaluate(&self, game: &ConnectFour, board: &Board, player: Color) -> i32 {
+            board[game.num_columns() / 2]
+                .iter()
+                .filter(|&&cell| cell == Cell::Filled(player))
+                .count() as i32
+        }
+    }
+
+    fn assert_paths_agree(game: &ConnectFour, evaluator: &dyn Evaluator, state: &GameState) {
+        let position = Position::from_state(game, state).unwrap();
+        for player in [Color::Red, Color::Yellow] {
+            assert_eq!(
+                evaluator.evaluate(game, &state.board, player),
+                evaluator.evaluate_position(game, &position, player)
+            );
+        }
+    }
+
+    #[test]
+    fn test_board_and_position_agree() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("4453627").unwrap();
+        let weights = EvaluationWeights {
+            center: 3,
+            one_short: 250,
+            ..EvaluationWeights::default()
+        };
+        assert_paths_agree(&game, &WeightedEvaluator::default(), &state);
+        assert_paths_agree(&game, &WeightedEvaluator::new(weights), &state);
+        assert_paths_agree(&game, &CenterOnly, &state);
+
+        let cylinder = ConnectFourConfig::new()
+            .topology(crate::Topology::Cylinder)
+            .build()
+            .unwrap();
+        let state = cylinder.state_from_moves("7711226").unwrap();
+        assert_paths_agree(&cylinder, &WeightedEvaluator::default(), &state);
+    }
+
+    #[test]
+    fn test_weights_from_json() {
+        let weights: EvaluationWeights = serde_json::from_str(r#"{"center": 20}"#).unwrap();
+        assert_eq!(weights.center, 20);
+        assert_eq!(weights.win, EvaluationWeights::default().win);
+
+        let config: ConnectFourConfig =
+            serde_json::from_str(r#"{"weights": {"opponent_one_short": 5}}"#).unwrap();
+        assert_eq!(config.build().unwrap().max_depth(), 5);
+    }
+
+    #[test]
+    fn test_custom_evaluator() {
+        let mut game = ConnectFourConfig::new().max_depth(2).build().unwrap();
+        game.set_evaluator(Box::new(CenterOnly));
+        let state = game.state_from_moves("11").unwrap();
+        // Only the center column matters to this evaluator
+        assert_eq!(
+            game.get_computer_move(&state, crate::Quality::Best),
+            crate::Move::Drop(3)
+        );
+    }
+}
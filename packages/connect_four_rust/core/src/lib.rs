@@ -1,14 +1,96 @@
 // core/src/lib.rs
 
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+use std::time::Duration;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+mod analysis;
+mod book;
+mod config;
+mod evaluation;
+mod limits;
+mod mcts;
+mod mnk;
+mod notation;
+mod perft;
+mod pop_ten;
+mod position;
+mod random;
+mod search;
+mod solver;
+mod strategy;
+mod tactics;
+mod topology;
+mod tt;
+mod variant;
+
+pub use analysis::ColumnAnalysis;
+pub use book::{BookError, OpeningBook};
+pub use config::{ConfigError, ConnectFourConfig, MAX_DIMENSION};
+pub use evaluation::{EvaluationWeights, Evaluator, WeightedEvaluator};
+#[cfg(not(target_arch = "wasm32"))]
+pub use limits::SystemClock;
+pub use limits::{Clock, SearchLimits};
+pub use mcts::MctsStrategy;
+pub use notation::{FenError, NotationError};
+pub use perft::PerftCounts;
+pub use pop_ten::{PopRecord, PopTenPhase, PopTenState, POP_TEN_TARGET};
+pub use position::Position;
+pub use solver::{Outcome, Solver};
+pub use strategy::{MinimaxStrategy, RandomStrategy, SolverStrategy, Strategy, TacticalStrategy};
+pub use tactics::{PlayerTactics, Tactics, Threat};
+pub use topology::Topology;
+pub use tt::{Bound, Entry, TranspositionTable};
+pub use variant::{Move, Variant};
+
+use limits::Budget;
+use search::Search;
+
+/// How many solver nodes `Quality::Perfect` may spend on a move before it falls
+/// back to the alpha-beta search. Early in a game on the standard board the
+/// solver needs far more, so without a book those moves come from the search.
+const PERFECT_NODES: u64 = 1_000_000;
+
+/// The players' colors, in turn order. Two-player games use red and yellow;
+/// games with more players add green and then blue.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Color {
     Red,
     Yellow,
+    Green,
+    Blue,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// Most players a game can have, one for each `Color`.
+pub const MAX_PLAYERS: usize = 4;
+
+impl Color {
+    /// Every color in turn order.
+    pub const ALL: [Color; MAX_PLAYERS] = [Color::Red, Color::Yellow, Color::Green, Color::Blue];
+
+    /// Position of the color in turn order, from 0.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The player after this one in a game of `num_players`.
+    pub fn next(self, num_players: usize) -> Color {
+        Color::ALL[(self.index() + 1) % num_players]
+    }
+
+    /// The player before this one in a game of `num_players`.
+    pub fn previous(self, num_players: usize) -> Color {
+        Color::ALL[(self.index() + num_players - 1) % num_players]
+    }
+
+    /// The other player of a two-player game.
+    pub(crate) fn opponent(self) -> Color {
+        self.next(2)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cell {
     Empty,
     Filled(Color),
@@ -22,34 +104,229 @@ pub enum Quality {
     Bad,
     Medium,
     Best,
+    Perfect,
+    Mcts,
+}
+
+/// Why a move was rejected by `ConnectFour::try_place_piece` or
+/// `ConnectFour::try_play`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// The column is not on the board.
+    ColumnOutOfRange {
+        column: Coordinate,
+        num_columns: usize,
+    },
+    /// The column has no empty cell left.
+    ColumnFull { column: Coordinate },
+    /// The game has already been won or drawn.
+    GameOver,
+    /// Pops are not part of the game's variant.
+    PopNotAllowed,
+    /// The bottom of the column does not hold one of the mover's pieces.
+    CannotPop { column: Coordinate },
+    /// The kind of move is not allowed in the current phase of the game.
+    WrongPhase,
+    /// Placing a piece in a chosen cell is only allowed without gravity.
+    PlaceNotAllowed,
+    /// Dropping a piece into a column is not allowed without gravity.
+    DropNotAllowed,
+    /// The row is not on the board.
+    RowOutOfRange { row: usize, num_rows: usize },
+    /// The cell already holds a piece.
+    CellOccupied { column: Coordinate, row: usize },
 }
 
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::ColumnOutOfRange {
+                column,
+                num_columns,
+            } => write!(
+                f,
+                "column {} is out of range (the board has {} columns)",
+                column, num_columns
+            ),
+            MoveError::ColumnFull { column } => write!(f, "column {} is full", column),
+            MoveError::GameOver => write!(f, "the game is already over"),
+            MoveError::PopNotAllowed => write!(f, "pops are not allowed in this variant"),
+            MoveError::CannotPop { column } => {
+                write!(
+                    f,
+                    "the bottom of column {} is not the player's piece",
+                    column
+                )
+            }
+            MoveError::WrongPhase => write!(f, "the move is not allowed in this phase of the game"),
+            MoveError::PlaceNotAllowed => {
+                write!(f, "pieces can only be dropped into columns in this variant")
+            }
+            MoveError::DropNotAllowed => {
+                write!(f, "pieces must be placed in a cell in this variant")
+            }
+            MoveError::RowOutOfRange { row, num_rows } => write!(
+                f,
+                "row {} is out of range (the board has {} rows)",
+                row, num_rows
+            ),
+            MoveError::CellOccupied { column, row } => {
+                write!(f, "the cell in column {} and row {} is taken", column, row)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// Why `ConnectFour::undo` or `ConnectFour::redo` could not be applied.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryError {
+    /// No move has been played, or the state was built without its history.
+    NothingToUndo,
+    /// No move has been taken back since the last move was played.
+    NothingToRedo,
+    /// The recorded moves do not match the board.
+    Inconsistent,
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::NothingToUndo => write!(f, "there is no move to undo"),
+            HistoryError::NothingToRedo => write!(f, "there is no move to redo"),
+            HistoryError::Inconsistent => write!(f, "the move history does not match the board"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GameState {
     pub board: Board,
     pub current_player: Color,
     pub winner: Option<Color>,
     pub is_game_over: bool,
+    /// Moves played so far, oldest first.
+    #[serde(default)]
+    pub moves: Vec<Move>,
+    /// Moves taken back by `undo`, most recent last, replayed by `redo`.
+    #[serde(default)]
+    pub undone_moves: Vec<Move>,
+    /// The phase and collected pieces of a `Variant::PopTen` game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pop_ten: Option<PopTenState>,
 }
 
+/// A game engine for a fixed board geometry. Build custom geometries with
+/// `ConnectFourConfig`.
 pub struct ConnectFour {
-    pub num_columns: usize,
-    pub num_rows: usize,
-    pub winning_length: usize,
-    pub max_depth: usize,
-    // pub state_cache: HashMap<String, Coordinate>, // Optional: For precomputed states
+    num_columns: usize,
+    num_rows: usize,
+    winning_length: usize,
+    max_depth: usize,
+    num_players: usize,
+    variant: Variant,
+    topology: Topology,
+    transposition_table: RefCell<TranspositionTable>,
+    solver: RefCell<Solver>,
+    opening_book: Option<OpeningBook>,
+    evaluator: Box<dyn Evaluator>,
+    rng: RefCell<random::GameRng>,
+    /// Every line of `winning_length` cells, from the topology.
+    lines: Vec<Vec<(Coordinate, usize)>>,
+    /// Bitmasks of `lines`, for boards that fit in a `Position`.
+    windows: Vec<u64>,
+}
+
+impl Default for ConnectFour {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ConnectFour {
     /// Creates a new ConnectFour instance with default settings.
     pub fn new() -> Self {
-        ConnectFour {
-            num_columns: 7,
-            num_rows: 6,
-            winning_length: 4,
-            max_depth: 5, // Adjusted depth for performance
-                          // state_cache: HashMap::new(),
-        }
+        ConnectFourConfig::new()
+            .build()
+            .expect("the default config is valid")
+    }
+
+    /// Number of columns on the board.
+    pub fn num_columns(&self) -> usize {
+        self.num_columns
+    }
+
+    /// Number of rows on the board.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Number of pieces in a line needed to win.
+    pub fn winning_length(&self) -> usize {
+        self.winning_length
+    }
+
+    /// Search depth used by `Quality::Best`.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Number of players, who take turns in the order of `Color::ALL`.
+    pub fn num_players(&self) -> usize {
+        self.num_players
+    }
+
+    /// The rules the game is played by.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// How the edges of the board connect.
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Replaces the transposition table with an empty one using at most `bytes`.
+    pub fn set_transposition_table_memory(&mut self, bytes: usize) {
+        self.transposition_table = RefCell::new(TranspositionTable::with_memory(bytes));
+    }
+
+    /// Sets the opening book consulted by `Quality::Best` and `Quality::Perfect`.
+    ///
+    /// A book built for a different board size is ignored.
+    pub fn set_opening_book(&mut self, book: Option<OpeningBook>) {
+        self.opening_book = book;
+    }
+
+    /// Replaces the evaluator used by the alpha-beta search. Clears the
+    /// transposition table, whose scores came from the old evaluator.
+    pub fn set_evaluator(&mut self, evaluator: Box<dyn Evaluator>) {
+        self.evaluator = evaluator;
+        self.clear_transposition_table();
+    }
+
+    /// Reseeds the random choices of `Quality::Bad`, `Quality::Medium` and
+    /// `Quality::Mcts`. The same seed gives the same moves on every platform.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = RefCell::new(random::seeded(seed));
+    }
+
+    /// Every line of `winning_length` cells on the board.
+    pub(crate) fn lines(&self) -> &[Vec<(Coordinate, usize)>] {
+        &self.lines
+    }
+
+    /// Bitmasks of every line of `winning_length` cells on a `Position` board.
+    pub(crate) fn windows(&self) -> &[u64] {
+        &self.windows
+    }
+
+    /// Forgets every cached search result.
+    pub fn clear_transposition_table(&self) {
+        self.transposition_table.borrow_mut().clear();
     }
 
     /// Creates an empty game board.
@@ -64,79 +341,187 @@ impl ConnectFour {
             current_player: Color::Red,
             winner: None,
             is_game_over: false,
+            moves: Vec::new(),
+            undone_moves: Vec::new(),
+            pop_ten: (self.variant == Variant::PopTen).then(PopTenState::default),
         }
     }
 
     /// Places a piece on the board at the given column for the current player.
-    /// Returns a new game state with the piece placed.
+    /// Returns a new game state with the piece placed, or the state unchanged if
+    /// the move is not allowed.
     pub fn place_piece(&self, state: &GameState, coordinate: Coordinate) -> GameState {
-        // Validate coordinate
-        if coordinate >= self.num_columns || state.is_game_over {
-            // Invalid coordinate or game over, return state unchanged
-            return state.clone();
+        self.try_place_piece(state, coordinate)
+            .unwrap_or_else(|_| state.clone())
+    }
+
+    /// Places a piece on the board at the given column for the current player.
+    /// Returns the new game state, or why the move is not allowed.
+    pub fn try_place_piece(
+        &self,
+        state: &GameState,
+        coordinate: Coordinate,
+    ) -> Result<GameState, MoveError> {
+        match self.variant {
+            Variant::Mnk => return Err(MoveError::DropNotAllowed),
+            Variant::PopTen => return self.try_play_pop_ten(state, Move::Drop(coordinate)),
+            _ => {}
+        }
+        if state.is_game_over {
+            return Err(MoveError::GameOver);
+        }
+        if coordinate >= self.num_columns {
+            return Err(MoveError::ColumnOutOfRange {
+                column: coordinate,
+                num_columns: self.num_columns,
+            });
         }
 
+        let row = state.board[coordinate]
+            .iter()
+            .rposition(|&cell| cell == Cell::Empty)
+            .ok_or(MoveError::ColumnFull { column: coordinate })?;
         let mut new_board = state.board.clone();
+        new_board[coordinate][row] = Cell::Filled(state.current_player);
 
-        for row in (0..self.num_rows).rev() {
-            if let Cell::Empty = new_board[coordinate][row] {
-                new_board[coordinate][row] = Cell::Filled(state.current_player);
+        Ok(self.finish_move(state, new_board, Move::Drop(coordinate)))
+    }
 
-                let winner = if self.check_winner(&new_board, state.current_player) {
-                    Some(state.current_player)
-                } else {
-                    None
-                };
+    /// Builds the state after `mv` turned `state.board` into `board`, deciding
+    /// the winner and whether the game is over by the rules of the variant.
+    fn finish_move(&self, state: &GameState, board: Board, mv: Move) -> GameState {
+        let player = state.current_player;
+        let next_player = player.next(self.num_players);
 
-                let is_game_over = winner.is_some() || self.is_board_full(&new_board);
+        // A pop can complete lines for both players; the mover's line counts first.
+        // Pops are only played by two players, so the other line is the next player's.
+        let winner = if self.check_winner(&board, player) {
+            // In misère completing a line loses
+            if self.variant == Variant::Misere {
+                Some(next_player)
+            } else {
+                Some(player)
+            }
+        } else if matches!(mv, Move::Pop { .. }) && self.check_winner(&board, next_player) {
+            Some(next_player)
+        } else {
+            None
+        };
 
-                return GameState {
-                    board: new_board,
-                    current_player: match state.current_player {
-                        Color::Red => Color::Yellow,
-                        Color::Yellow => Color::Red,
-                    },
-                    winner,
-                    is_game_over,
-                };
+        let mut moves = state.moves.clone();
+        moves.push(mv);
+
+        let mut new_state = GameState {
+            board,
+            current_player: next_player,
+            winner,
+            is_game_over: winner.is_some(),
+            moves,
+            undone_moves: Vec::new(),
+            pop_ten: None,
+        };
+        new_state.is_game_over = winner.is_some() || self.is_draw(&new_state);
+        new_state
+    }
+
+    /// Takes back the last move. The move can be replayed with `redo` until a
+    /// new move is played.
+    pub fn undo(&self, state: &GameState) -> Result<GameState, HistoryError> {
+        if self.variant == Variant::PopTen {
+            return self.undo_pop_ten(state);
+        }
+        let &mv = state.moves.last().ok_or(HistoryError::NothingToUndo)?;
+        let previous_player = state.current_player.previous(self.num_players);
+
+        let mut new_state = state.clone();
+        self.take_back(&mut new_state.board, mv, previous_player)?;
+        new_state.current_player = previous_player;
+        new_state.winner = None;
+        new_state.is_game_over = false;
+        new_state.moves.pop();
+        new_state.undone_moves.push(mv);
+        Ok(new_state)
+    }
+
+    /// Reverses `mv`, played by `player`, on the board.
+    fn take_back(&self, board: &mut Board, mv: Move, player: Color) -> Result<(), HistoryError> {
+        let column = board
+            .get_mut(mv.column())
+            .ok_or(HistoryError::Inconsistent)?;
+        match mv {
+            Move::Drop(_) => {
+                let row = column
+                    .iter()
+                    .position(|&cell| cell != Cell::Empty)
+                    .ok_or(HistoryError::Inconsistent)?;
+                if column[row] != Cell::Filled(player) {
+                    return Err(HistoryError::Inconsistent);
+                }
+                column[row] = Cell::Empty;
+            }
+            Move::Pop { .. } => {
+                if column.first() != Some(&Cell::Empty) {
+                    return Err(HistoryError::Inconsistent);
+                }
+                // Push the popped piece back in from the bottom
+                column.remove(0);
+                column.push(Cell::Filled(player));
+            }
+            Move::Place { row, .. } => {
+                if column.get(row) != Some(&Cell::Filled(player)) {
+                    return Err(HistoryError::Inconsistent);
+                }
+                column[row] = Cell::Empty;
             }
         }
+        Ok(())
+    }
 
-        // Column is full, return state unchanged
-        state.clone()
+    /// Replays the most recently undone move.
+    pub fn redo(&self, state: &GameState) -> Result<GameState, HistoryError> {
+        let &mv = state
+            .undone_moves
+            .last()
+            .ok_or(HistoryError::NothingToRedo)?;
+        let mut new_state = self
+            .try_play(state, mv)
+            .map_err(|_| HistoryError::Inconsistent)?;
+        new_state.undone_moves = state.undone_moves[..state.undone_moves.len() - 1].to_vec();
+        Ok(new_state)
     }
 
     /// Checks if there's a winner on the board for a specific player.
     pub fn check_winner(&self, board: &Board, player: Color) -> bool {
-        let directions = vec![
-            (1, 0),  // Horizontal
-            (0, 1),  // Vertical
-            (1, 1),  // Diagonal down-right
-            (1, -1), // Diagonal up-right
-        ];
+        if Position::fits(self.num_columns, self.num_rows) {
+            let stones = position::board_stones(board, self.num_rows, player);
+            return match self.topology {
+                Topology::Flat => {
+                    position::has_alignment(stones, self.num_rows, self.winning_length)
+                }
+                Topology::Cylinder => self.windows.iter().any(|&window| window & !stones == 0),
+            };
+        }
 
+        let piece = Cell::Filled(player);
         for c in 0..self.num_columns {
             for r in 0..self.num_rows {
-                if board[c][r] != Cell::Filled(player) {
+                if board[c][r] != piece {
                     continue;
                 }
 
-                for &(dc, dr) in &directions {
+                for &(dc, dr) in &topology::DIRECTIONS {
                     let mut count = 1;
-                    let mut cc = c as isize + dc;
-                    let mut rr = r as isize + dr;
-                    while cc >= 0
-                        && cc < self.num_columns as isize
-                        && rr >= 0
-                        && rr < self.num_rows as isize
-                        && board[cc as usize][rr as usize] == Cell::Filled(player)
-                    {
+                    let mut next = self.neighbor(c, r, dc, dr);
+                    while let Some((cc, rr)) = next {
+                        // Stop at a piece that isn't the player's, or back at the start
+                        if board[cc][rr] != piece || (cc, rr) == (c, r) {
+                            break;
+                        }
                         count += 1;
                         if count == self.winning_length {
                             return true;
                         }
-                        cc += dc;
-                        rr += dr;
+                        next = self.neighbor(cc, rr, dc, dr);
                     }
                 }
             }
@@ -145,90 +530,251 @@ impl ConnectFour {
         false
     }
 
-    /// Gets the computer's move based on the specified quality.
-    pub fn get_computer_move(&self, state: &GameState, quality: Quality) -> Coordinate {
-        let valid_columns = self.get_valid_columns(&state.board);
+    /// The cell one step from `(col, row)` in a direction, if it is on the board.
+    fn neighbor(
+        &self,
+        col: Coordinate,
+        row: usize,
+        dc: isize,
+        dr: isize,
+    ) -> Option<(Coordinate, usize)> {
+        self.topology.cell(
+            self.num_columns,
+            self.num_rows,
+            col as isize + dc,
+            row as isize + dr,
+        )
+    }
 
-        match quality {
-            Quality::Bad => self.get_random_column(&valid_columns),
-            Quality::Medium => {
-                // Try to win in the next move
-                for &col in &valid_columns {
-                    let temp_state = self.place_piece(state, col);
-                    if temp_state.winner == Some(state.current_player) {
-                        return col;
-                    }
-                }
+    /// Finds every line of at least `winning_length` pieces of `player`.
+    ///
+    /// Each line lists its cells as `(column, row)` pairs in board coordinates,
+    /// with row 0 at the top. Lines longer than `winning_length` are reported
+    /// once with all their cells, and a single move can complete several lines.
+    pub fn find_winning_lines(
+        &self,
+        board: &Board,
+        player: Color,
+    ) -> Vec<Vec<(Coordinate, usize)>> {
+        let piece = Cell::Filled(player);
+        let is_player =
+            |cell: Option<(Coordinate, usize)>| cell.is_some_and(|(c, r)| board[c][r] == piece);
 
-                // Block opponent's winning move
-                let opponent = match state.current_player {
-                    Color::Red => Color::Yellow,
-                    Color::Yellow => Color::Red,
-                };
-                for &col in &valid_columns {
-                    let mut temp_state = state.clone();
-                    temp_state.current_player = opponent;
-                    let temp_state = self.place_piece(&temp_state, col);
-                    if temp_state.winner == Some(opponent) {
-                        return col;
+        let mut lines = Vec::new();
+        for (c, column) in board.iter().enumerate() {
+            for (r, &cell) in column.iter().enumerate() {
+                if cell != piece {
+                    continue;
+                }
+                for &(dc, dr) in &topology::DIRECTIONS {
+                    let mut line = vec![(c, r)];
+                    let mut next = self.neighbor(c, r, dc, dr);
+                    while is_player(next) && next != Some((c, r)) {
+                        let (cc, rr) = next.unwrap();
+                        line.push((cc, rr));
+                        next = self.neighbor(cc, rr, dc, dr);
+                    }
+                    // Only start from the first cell of each run; a run all the
+                    // way around a cylinder starts from its smallest cell
+                    let ring = next == Some((c, r));
+                    if ring {
+                        if line.iter().min() != Some(&(c, r)) {
+                            continue;
+                        }
+                    } else if is_player(self.neighbor(c, r, -dc, -dr)) {
+                        continue;
+                    }
+                    if line.len() >= self.winning_length {
+                        lines.push(line);
                     }
                 }
-
-                // Else, pick a random column
-                self.get_random_column(&valid_columns)
             }
-            Quality::Best => self.get_best_move(state),
         }
+        lines
     }
 
-    /// Gets a list of valid columns where a piece can be placed.
+    /// Gets the computer's move based on the specified quality.
+    ///
+    /// The strategy of `quality` drops a piece. In variants where the move may
+    /// be a pop or a placement it comes from `get_variant_move` instead.
+    pub fn get_computer_move(&self, state: &GameState, quality: Quality) -> Move {
+        if !self.drops_only() {
+            return self.get_variant_move(state, quality);
+        }
+        Move::Drop(quality.strategy().choose_move(self, state))
+    }
+
+    /// Looks the position up in the opening book.
+    fn get_book_move(&self, state: &GameState) -> Option<Coordinate> {
+        let book = self
+            .opening_book
+            .as_ref()
+            .filter(|book| book.matches(self))?;
+        book.get(&Position::from_state(self, state)?)
+    }
+
+    /// Solves the position exactly and returns its outcome for the player to move.
+    ///
+    /// Returns `None` for boards that do not fit in a `Position`.
+    pub fn solve(&self, state: &GameState) -> Option<Outcome> {
+        let position = Position::from_state(self, state)?;
+        Some(self.solver.borrow_mut().solve(&position))
+    }
+
+    /// Uses the exact solver to pick a move, falling back to `get_best_move` for
+    /// boards that do not fit in a `Position` and for positions the solver cannot
+    /// finish within `PERFECT_NODES` nodes.
+    fn get_perfect_move(&self, state: &GameState) -> Coordinate {
+        Position::from_state(self, state)
+            .and_then(|position| {
+                self.solver
+                    .borrow_mut()
+                    .best_move_within(&position, PERFECT_NODES)
+            })
+            .unwrap_or_else(|| self.get_best_move(state))
+    }
+
+    /// Gets the computer's best move, searching deeper until the time budget runs out.
+    ///
+    /// Uses `std::time::Instant`; on wasm32 use `get_computer_move_with_limits` with a
+    /// clock provided by the host.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn get_computer_move_timed(&self, state: &GameState, budget: Duration) -> Move {
+        self.get_computer_move_with_limits(state, SearchLimits::time(budget), &SystemClock::new())
+    }
+
+    /// Gets the computer's best move using iterative deepening within the limits.
+    ///
+    /// Returns the best move of the deepest iteration that completed. The depth-zero
+    /// iteration always completes, so a move is returned even with a zero budget.
+    /// Games a `Position` cannot hold are searched on the board within the same
+    /// limits, and variants with pops or placements by
+    /// `get_variant_move_with_limits`.
+    pub fn get_computer_move_with_limits(
+        &self,
+        state: &GameState,
+        limits: SearchLimits,
+        clock: &dyn Clock,
+    ) -> Move {
+        if !self.drops_only() {
+            return self.get_variant_move_with_limits(state, limits, clock);
+        }
+        if let Some(col) = self.get_book_move(state) {
+            return Move::Drop(col);
+        }
+        let max_depth = limits.max_depth(self.max_depth);
+        let col = match Position::from_state(self, state) {
+            Some(position) => {
+                let mut table = self.transposition_table.borrow_mut();
+                Search::new(self, &mut table, &position)
+                    .with_limits(&limits, clock)
+                    .iterative_deepening(&position, max_depth)
+            }
+            None => self.iterative_deepening_on_board(
+                state,
+                max_depth,
+                &mut Budget::new(&limits, clock),
+            ),
+        };
+        Move::Drop(col)
+    }
+
+    /// Gets a list of valid columns where a piece can be dropped. There are none
+    /// in `Variant::Mnk`, where pieces are placed in cells instead.
     fn get_valid_columns(&self, board: &Board) -> Vec<Coordinate> {
+        if self.variant == Variant::Mnk {
+            return Vec::new();
+        }
         (0..self.num_columns)
             .filter(|&c| matches!(board[c][0], Cell::Empty))
             .collect()
     }
 
+    /// A generator for a search with its own random stream, drawn from the
+    /// game's so that seeded games repeat.
+    pub(crate) fn fork_rng(&self) -> random::GameRng {
+        random::fork(&mut self.rng.borrow_mut())
+    }
+
     /// Gets a random column from the list of valid columns.
     fn get_random_column(&self, valid_columns: &[Coordinate]) -> Coordinate {
-        use rand::seq::SliceRandom;
-        use rand::thread_rng;
-
-        let mut rng = thread_rng();
-        *valid_columns
-            .choose(&mut rng)
-            .expect("No valid columns available")
+        assert!(!valid_columns.is_empty(), "No valid columns available");
+        let index = random::index(&mut self.rng.borrow_mut(), valid_columns.len());
+        valid_columns[index]
     }
 
     /// Uses the minimax algorithm with alpha-beta pruning to determine the best move.
     fn get_best_move(&self, state: &GameState) -> Coordinate {
-        let opponent = match state.current_player {
-            Color::Red => Color::Yellow,
-            Color::Yellow => Color::Red,
-        };
+        if let Some(position) = Position::from_state(self, state) {
+            let mut table = self.transposition_table.borrow_mut();
+            return Search::new(self, &mut table, &position).best_move(&position);
+        }
+        self.get_best_move_on_board(state)
+    }
 
-        let valid_columns = self.get_valid_columns(&state.board);
+    /// Board-based fallback for `get_best_move` on games a `Position` cannot hold:
+    /// boards too large for a bitboard and games of more than two players.
+    fn get_best_move_on_board(&self, state: &GameState) -> Coordinate {
+        self.search_board_root(state, self.max_depth, &mut Budget::unlimited())
+            .expect("No valid columns available")
+    }
+
+    /// Board-based fallback for `get_computer_move_with_limits`: searches one
+    /// depth at a time and returns the best move from the deepest iteration that
+    /// finished within the budget.
+    fn iterative_deepening_on_board(
+        &self,
+        state: &GameState,
+        max_depth: usize,
+        budget: &mut Budget,
+    ) -> Coordinate {
+        let max_depth = max_depth.min(self.empty_cells(&state.board).saturating_sub(1));
+
+        let mut best_column = None;
+        for depth in 0..=max_depth {
+            match self.search_board_root(state, depth, budget) {
+                Some(col) => best_column = Some(col),
+                None => break,
+            }
+            budget.allow_abort();
+        }
+
+        best_column.expect("No valid columns available")
+    }
+
+    /// Scores every column to `depth` and returns the best one.
+    ///
+    /// Returns `None` if no column is playable or the search ran out of budget.
+    fn search_board_root(
+        &self,
+        state: &GameState,
+        depth: usize,
+        budget: &mut Budget,
+    ) -> Option<Coordinate> {
         let mut best_score = i32::MIN;
-        let mut best_column = valid_columns[0];
+        let mut best_column = None;
 
         // Move ordering: prioritize center column and adjacent columns
         let center = self.num_columns / 2;
-        let mut ordered_columns = valid_columns.clone();
+        let mut ordered_columns = self.get_valid_columns(&state.board);
         ordered_columns.sort_by_key(|&col| (center as isize - col as isize).abs());
 
-        for &col in &ordered_columns {
+        for col in ordered_columns {
             let new_state = self.place_piece(state, col);
             let score = self.minimax(
                 &new_state,
-                self.max_depth,
+                depth,
                 i32::MIN,
                 i32::MAX,
-                false,
                 state.current_player,
-                opponent,
+                budget,
             );
-            if score > best_score {
+            if budget.aborted() {
+                return None;
+            }
+            if best_column.is_none() || score > best_score {
                 best_score = score;
-                best_column = col;
+                best_column = Some(col);
             }
         }
 
@@ -236,28 +782,37 @@ impl ConnectFour {
     }
 
     /// Minimax algorithm with alpha-beta pruning and depth limiting.
+    ///
+    /// Only used for games a `Position` cannot hold; see `search::Search`. With
+    /// more than two players the search is paranoid: `player` maximizes and
+    /// every other player is assumed to play against them. Returns 0 once the
+    /// budget runs out.
     fn minimax(
         &self,
         state: &GameState,
         depth: usize,
         mut alpha: i32,
         mut beta: i32,
-        is_maximizing: bool,
         player: Color,
-        opponent: Color,
+        budget: &mut Budget,
     ) -> i32 {
+        if budget.visit() {
+            return 0;
+        }
         if depth == 0 || state.is_game_over {
-            return self.evaluate_board(&state.board, player, opponent);
+            return self.evaluator.evaluate(self, &state.board, player);
         }
 
         let valid_columns = self.get_valid_columns(&state.board);
 
-        if is_maximizing {
+        if state.current_player == player {
             let mut max_eval = i32::MIN;
             for &col in &valid_columns {
                 let new_state = self.place_piece(state, col);
-                let eval =
-                    self.minimax(&new_state, depth - 1, alpha, beta, false, player, opponent);
+                let eval = self.minimax(&new_state, depth - 1, alpha, beta, player, budget);
+                if budget.aborted() {
+                    return 0;
+                }
                 max_eval = max_eval.max(eval);
                 alpha = alpha.max(eval);
                 if beta <= alpha {
@@ -269,7 +824,10 @@ impl ConnectFour {
             let mut min_eval = i32::MAX;
             for &col in &valid_columns {
                 let new_state = self.place_piece(state, col);
-                let eval = self.minimax(&new_state, depth - 1, alpha, beta, true, player, opponent);
+                let eval = self.minimax(&new_state, depth - 1, alpha, beta, player, budget);
+                if budget.aborted() {
+                    return 0;
+                }
                 min_eval = min_eval.min(eval);
                 beta = beta.min(eval);
                 if beta <= alpha {
@@ -280,119 +838,19 @@ impl ConnectFour {
         }
     }
 
-    /// Evaluates the board and returns a score.
-    fn evaluate_board(&self, board: &Board, player: Color, opponent: Color) -> i32 {
-        let mut score = 0;
-
-        // Score center column
-        let center_col = self.num_columns / 2;
-        let center_array = &board[center_col];
-        let center_count = center_array
-            .iter()
-            .filter(|&&cell| cell == Cell::Filled(player))
-            .count();
-        score += (center_count as i32) * 6;
-
-        // Score positions in all directions
-        score += self.score_direction(board, player, opponent, 1, 0); // Horizontal
-        score += self.score_direction(board, player, opponent, 0, 1); // Vertical
-        score += self.score_direction(board, player, opponent, 1, 1); // Diagonal /
-        score += self.score_direction(board, player, opponent, 1, -1); // Diagonal \
-
-        score
-    }
-
-    /// Scores the board in a specific direction.
-    fn score_direction(
-        &self,
-        board: &Board,
-        player: Color,
-        opponent: Color,
-        dc: isize,
-        dr: isize,
-    ) -> i32 {
-        let mut score = 0;
-
-        for c in 0..self.num_columns {
-            for r in 0..self.num_rows {
-                let mut window_cells = Vec::new();
-                for i in 0..self.winning_length {
-                    let cc = c as isize + i as isize * dc;
-                    let rr = r as isize + i as isize * dr;
-                    if cc >= 0
-                        && cc < self.num_columns as isize
-                        && rr >= 0
-                        && rr < self.num_rows as isize
-                    {
-                        window_cells.push(board[cc as usize][rr as usize]);
-                    }
-                }
-                if window_cells.len() == self.winning_length {
-                    score += self.evaluate_window(&window_cells, player, opponent);
-                }
-            }
-        }
-
-        score
-    }
-
-    /// Evaluates a window of cells and returns a score.
-    fn evaluate_window(&self, window_cells: &[Cell], player: Color, opponent: Color) -> i32 {
-        let mut score = 0;
-        let player_count = window_cells
-            .iter()
-            .filter(|&&cell| cell == Cell::Filled(player))
-            .count();
-        let opponent_count = window_cells
-            .iter()
-            .filter(|&&cell| cell == Cell::Filled(opponent))
-            .count();
-        let empty_count = window_cells
+    /// Counts the empty cells of the board.
+    fn empty_cells(&self, board: &Board) -> usize {
+        board
             .iter()
+            .flatten()
             .filter(|&&cell| cell == Cell::Empty)
-            .count();
-
-        if player_count == 4 {
-            score += 100000; // Winning move
-        } else if player_count == 3 && empty_count == 1 {
-            score += 100; // Three in a row with an open spot
-        } else if player_count == 2 && empty_count == 2 {
-            score += 10; // Two in a row with two open spots
-        }
-
-        if opponent_count == 4 {
-            score -= 100000; // Opponent's winning move
-        } else if opponent_count == 3 && empty_count == 1 {
-            score -= 1000; // Block opponent's three in a row
-        } else if opponent_count == 2 && empty_count == 2 {
-            score -= 10; // Block opponent's two in a row
-        }
-
-        score
+            .count()
     }
 
     /// Checks if the board is full.
     fn is_board_full(&self, board: &Board) -> bool {
         board.iter().all(|col| col[0] != Cell::Empty)
     }
-
-    /// Serializes the game state into a unique string representation.
-    fn serialize_state(&self, state: &GameState) -> String {
-        state
-            .board
-            .iter()
-            .map(|col| {
-                col.iter()
-                    .map(|&cell| match cell {
-                        Cell::Empty => '0',
-                        Cell::Filled(Color::Red) => 'R',
-                        Cell::Filled(Color::Yellow) => 'Y',
-                    })
-                    .collect::<String>()
-            })
-            .collect::<Vec<String>>()
-            .join("|")
-    }
 }
 
 #[cfg(test)]
@@ -422,6 +880,31 @@ mod tests {
         assert_eq!(new_state.current_player, Color::Yellow);
     }
 
+    #[test]
+    fn test_try_place_piece_errors() {
+        let game = ConnectFour::new();
+        let mut state = game.create_initial_state();
+        assert_eq!(
+            game.try_place_piece(&state, 7),
+            Err(MoveError::ColumnOutOfRange {
+                column: 7,
+                num_columns: 7
+            })
+        );
+        for _ in 0..game.num_rows {
+            state = game.try_place_piece(&state, 2).unwrap();
+        }
+        assert_eq!(
+            game.try_place_piece(&state, 2),
+            Err(MoveError::ColumnFull { column: 2 })
+        );
+        for &col in &[0, 1, 0, 1, 0, 1, 0] {
+            state = game.try_place_piece(&state, col).unwrap();
+        }
+        assert!(state.is_game_over);
+        assert_eq!(game.try_place_piece(&state, 3), Err(MoveError::GameOver));
+    }
+
     #[test]
     fn test_winner_horizontal() {
         let game = ConnectFour::new();
@@ -471,4 +954,360 @@ mod tests {
         let new_state = game.place_piece(&state, 0);
         assert_eq!(state, new_state);
     }
+
+    #[test]
+    fn test_bitboard_search_matches_board_search() {
+        let game = ConnectFourConfig::new().max_depth(3).build().unwrap();
+        let mut state = game.create_initial_state();
+        for &col in &[3, 3, 4, 2, 5, 1] {
+            state = game.place_piece(&state, col);
+            assert_eq!(
+                game.get_best_move(&state),
+                game.get_best_move_on_board(&state)
+            );
+        }
+    }
+
+    #[test]
+    fn test_transposition_table_reused_across_moves() {
+        let mut game = ConnectFourConfig::new().max_depth(4).build().unwrap();
+        game.set_transposition_table_memory(4096);
+        let mut state = game.create_initial_state();
+        for &col in &[3, 2, 3, 3, 4] {
+            state = game.place_piece(&state, col);
+            let expected = game.get_best_move_on_board(&state);
+            assert_eq!(game.get_best_move(&state), expected);
+            // A warm table must not change the answer
+            assert_eq!(game.get_best_move(&state), expected);
+        }
+    }
+
+    #[test]
+    fn test_even_width_table_keeps_orientation() {
+        let game = ConnectFourConfig::new().num_columns(8).build().unwrap();
+        assert!(!game.evaluator.is_symmetric(&game));
+        assert!(WeightedEvaluator::default().is_symmetric(&ConnectFour::new()));
+
+        // The center bonus favors one side, so a mirror image searched after
+        // the original must not reuse its scores
+        let scores = |moves: &str| {
+            let state = game.state_from_moves(moves).unwrap();
+            let root = Position::from_state(&game, &state).unwrap();
+            let mut table = game.transposition_table.borrow_mut();
+            Search::new(&game, &mut table, &root).analyze(&root, 5)
+        };
+        let cold = scores("12");
+        game.clear_transposition_table();
+        scores("87");
+        assert_eq!(scores("12"), cold);
+    }
+
+    struct FrozenClock;
+
+    impl Clock for FrozenClock {
+        fn now(&self) -> Duration {
+            Duration::ZERO
+        }
+    }
+
+    #[test]
+    fn test_limited_search_matches_fixed_depth() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("4435").unwrap();
+        let limits = SearchLimits::depth(game.max_depth());
+        let expected = Move::Drop(game.get_best_move_on_board(&state));
+        assert_eq!(
+            game.get_computer_move_with_limits(&state, limits, &FrozenClock),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_zero_budget_still_moves() {
+        let game = ConnectFour::new();
+        let state = game.create_initial_state();
+        let col = game.get_computer_move_timed(&state, Duration::ZERO);
+        assert!(col.column() < game.num_columns);
+        let col = game.get_computer_move_with_limits(&state, SearchLimits::nodes(1), &FrozenClock);
+        assert!(col.column() < game.num_columns);
+    }
+
+    #[test]
+    fn test_limits_on_large_board() {
+        let config = ConnectFourConfig::new().num_columns(10).num_rows(8);
+        let game = config.clone().build().unwrap();
+        let state = game.state_from_moves("5566").unwrap();
+        let shallow = config.max_depth(2).build().unwrap();
+        assert_eq!(
+            game.get_computer_move_with_limits(&state, SearchLimits::depth(2), &FrozenClock),
+            Move::Drop(shallow.get_best_move_on_board(&state))
+        );
+
+        // Node and time limits stop the board search too
+        let col = game.get_computer_move_with_limits(&state, SearchLimits::nodes(1), &FrozenClock);
+        assert!(col.column() < game.num_columns);
+        let col = game.get_computer_move_timed(&state, Duration::ZERO);
+        assert!(col.column() < game.num_columns);
+    }
+
+    #[test]
+    fn test_default_limits_use_max_depth() {
+        let config = ConnectFourConfig::new().num_columns(10).num_rows(8);
+        let game = config.max_depth(3).build().unwrap();
+        let state = game.state_from_moves("5566").unwrap();
+        // Without any limit the search stops at the configured depth
+        assert_eq!(
+            game.get_computer_move_with_limits(&state, SearchLimits::default(), &FrozenClock),
+            Move::Drop(game.get_best_move_on_board(&state))
+        );
+
+        let standard = ConnectFour::new();
+        let col = standard.get_computer_move_with_limits(
+            &standard.state_from_moves("44").unwrap(),
+            SearchLimits::default(),
+            &FrozenClock,
+        );
+        assert!(col.column() < standard.num_columns);
+    }
+
+    #[test]
+    fn test_timed_search_blocks_open_three() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("17273").unwrap();
+        // Yellow must block the open three
+        assert_eq!(
+            game.get_computer_move_timed(&state, Duration::from_millis(50)),
+            Move::Drop(3)
+        );
+    }
+
+    #[test]
+    fn test_perfect_quality() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("44553").unwrap();
+        // Red threatens both ends of the bottom row, so Yellow is lost
+        assert_eq!(game.solve(&state), Some(Outcome::Loss { moves: 2 }));
+        let mv = game.get_computer_move(&state, Quality::Perfect);
+        let state = game.try_play(&state, mv).unwrap();
+        let mv = game.get_computer_move(&state, Quality::Perfect);
+        assert_eq!(game.try_play(&state, mv).unwrap().winner, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_perfect_quality_falls_back_to_search() {
+        let game = ConnectFour::new();
+        // Far too early for the solver to finish within its budget
+        let state = game.state_from_moves("4444").unwrap();
+        assert_eq!(
+            game.get_computer_move(&state, Quality::Perfect),
+            Move::Drop(game.get_best_move(&state))
+        );
+    }
+
+    #[test]
+    fn test_opening_book_lookup() {
+        let mut game = ConnectFour::new();
+        let state = game.create_initial_state();
+        let position = Position::from_state(&game, &state).unwrap();
+        let mut book = OpeningBook::new(&game);
+        book.insert(&position, 0);
+        game.set_opening_book(Some(book));
+        assert_eq!(game.get_computer_move(&state, Quality::Best), Move::Drop(0));
+        assert_eq!(
+            game.get_computer_move_timed(&state, Duration::ZERO),
+            Move::Drop(0)
+        );
+
+        // Positions outside the book fall through to the search
+        let state = game.place_piece(&state, 0);
+        assert_eq!(game.get_computer_move(&state, Quality::Best), Move::Drop(3));
+    }
+
+    #[test]
+    fn test_custom_geometries() {
+        for (columns, rows, length) in [(8, 7, 4), (9, 7, 4), (8, 7, 5)] {
+            let game = ConnectFourConfig::new()
+                .num_columns(columns)
+                .num_rows(rows)
+                .winning_length(length)
+                .max_depth(2)
+                .build()
+                .unwrap();
+            let mut state = game.create_initial_state();
+            assert_eq!(state.board.len(), columns);
+            assert_eq!(state.board[0].len(), rows);
+
+            // Red builds a horizontal line along the bottom, Yellow stacks in the last column
+            for col in 0..length {
+                assert!(state.winner.is_none());
+                state = game.try_place_piece(&state, col).unwrap();
+                if col + 1 < length {
+                    state = game.try_place_piece(&state, columns - 1).unwrap();
+                }
+            }
+            assert_eq!(state.winner, Some(Color::Red));
+        }
+    }
+
+    #[test]
+    fn test_connect_five_search_blocks_four() {
+        let game = ConnectFourConfig::new()
+            .num_columns(8)
+            .num_rows(7)
+            .winning_length(5)
+            .max_depth(2)
+            .build()
+            .unwrap();
+        let mut state = game.create_initial_state();
+        for &col in &[1, 7, 2, 7, 3, 6, 4] {
+            state = game.place_piece(&state, col);
+        }
+        // Red threatens five on both ends; Yellow blocks one of them
+        let col = game.get_computer_move(&state, Quality::Best).column();
+        assert!(col == 0 || col == 5);
+        assert_eq!(
+            game.get_best_move(&state),
+            game.get_best_move_on_board(&state)
+        );
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let game = ConnectFour::new();
+        let mut states = vec![game.create_initial_state()];
+        for &col in &[0, 0, 1, 1, 2, 2, 3] {
+            let next = game.place_piece(states.last().unwrap(), col);
+            states.push(next);
+        }
+        let won = states.last().unwrap().clone();
+        assert_eq!(won.winner, Some(Color::Red));
+        assert_eq!(won.moves, [0, 0, 1, 1, 2, 2, 3].map(Move::Drop));
+
+        let mut state = game.undo(&won).unwrap();
+        assert!(!state.is_game_over);
+        assert_eq!(state.winner, None);
+        assert_eq!(state.current_player, Color::Red);
+        state = game.undo(&state).unwrap();
+        state = game.undo(&state).unwrap();
+        assert_eq!(state.board, states[4].board);
+        assert_eq!(state.moves, states[4].moves);
+        assert_eq!(state.current_player, Color::Red);
+        assert_eq!(state.undone_moves, [3, 2, 2].map(Move::Drop));
+
+        state = game.redo(&state).unwrap();
+        state = game.redo(&state).unwrap();
+        assert_eq!(state.undone_moves, [3].map(Move::Drop));
+        state = game.redo(&state).unwrap();
+        assert_eq!(state, won);
+        assert_eq!(game.redo(&state), Err(HistoryError::NothingToRedo));
+
+        // Playing a new move drops the redo history
+        let state = game.place_piece(&game.undo(&won).unwrap(), 6);
+        assert!(state.undone_moves.is_empty());
+        assert_eq!(game.redo(&state), Err(HistoryError::NothingToRedo));
+
+        let fresh = game.create_initial_state();
+        assert_eq!(game.undo(&fresh), Err(HistoryError::NothingToUndo));
+    }
+
+    #[test]
+    fn test_find_winning_lines() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("121212").unwrap();
+        assert!(game.find_winning_lines(&state.board, Color::Red).is_empty());
+
+        // Red's next stone in column 4 completes a row and a diagonal at once
+        let state = game.state_from_fen("7/7/6r/5ry/4ryy/rrr1yyy r").unwrap();
+        let state = game.place_piece(&state, 3);
+        assert_eq!(state.winner, Some(Color::Red));
+        assert_eq!(
+            game.find_winning_lines(&state.board, Color::Red),
+            vec![
+                vec![(0, 5), (1, 5), (2, 5), (3, 5)],
+                vec![(3, 5), (4, 4), (5, 3), (6, 2)],
+            ]
+        );
+        assert!(game
+            .find_winning_lines(&state.board, Color::Yellow)
+            .is_empty());
+
+        // Longer lines are reported whole
+        let state = game.state_from_fen("7/7/7/7/yyy4/rrrrry1 y").unwrap();
+        assert_eq!(game.find_winning_lines(&state.board, Color::Red).len(), 1);
+        assert_eq!(
+            game.find_winning_lines(&state.board, Color::Red)[0].len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_seeded_games_repeat() {
+        let play = |seed| {
+            let game = ConnectFourConfig::new().seed(seed).build().unwrap();
+            let mut state = game.create_initial_state();
+            while !state.is_game_over {
+                let quality = match state.current_player {
+                    Color::Red => Quality::Bad,
+                    _ => Quality::Medium,
+                };
+                state = game
+                    .try_play(&state, game.get_computer_move(&state, quality))
+                    .unwrap();
+            }
+            state.to_move_string()
+        };
+        assert_eq!(play(42), play(42));
+        // Pinned so that a change to the random stream, or a platform where it
+        // differs, is noticed
+        assert_eq!(play(42), "2527635635223326566255773144");
+    }
+
+    #[test]
+    fn test_three_player_turns_and_win() {
+        let game = ConnectFourConfig::new()
+            .num_columns(9)
+            .num_rows(7)
+            .num_players(3)
+            .max_depth(3)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("12").unwrap();
+        assert_eq!(state.current_player, Color::Green);
+        assert_eq!(game.undo(&state).unwrap().current_player, Color::Yellow);
+
+        // Green stacks four in the third column while the others spread out
+        let state = game.state_from_moves("123453673893").unwrap();
+        assert_eq!(state.winner, Some(Color::Green));
+        assert!(state.is_game_over);
+        assert_eq!(state.current_player, Color::Red);
+        assert_eq!(
+            game.find_winning_lines(&state.board, Color::Green),
+            vec![vec![(2, 3), (2, 4), (2, 5), (2, 6)]]
+        );
+
+        let undone = game.undo(&state).unwrap();
+        assert_eq!(undone.current_player, Color::Green);
+        assert_eq!(undone.winner, None);
+    }
+
+    #[test]
+    fn test_three_player_ai_blocks_next_player() {
+        let game = ConnectFourConfig::new()
+            .num_columns(9)
+            .num_rows(7)
+            .num_players(3)
+            .max_depth(3)
+            .build()
+            .unwrap();
+        // Yellow, who moves after Red, threatens the fifth column
+        let state = game.state_from_fen("9/9/9/9/9/5rg2/gyyy1rgr1 r").unwrap();
+        assert_eq!(game.tactics(&state).forced_blocks, vec![4]);
+        assert_eq!(
+            game.tactics(&state).player(Color::Green).player,
+            Color::Green
+        );
+        for quality in [Quality::Medium, Quality::Best] {
+            assert_eq!(game.get_computer_move(&state, quality), Move::Drop(4));
+        }
+    }
 }
@@ -0,0 +1,165 @@
+// core/src/limits.rs
+
+use std::time::Duration;
+
+/// Bounds on how much work a search may do. `None` means unbounded.
+///
+/// `depth` counts plies below the move being chosen, matching
+/// `ConnectFour::max_depth`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    /// Limits the search to a fixed depth.
+    pub fn depth(depth: usize) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    /// Limits the search to a number of visited nodes.
+    pub fn nodes(nodes: u64) -> Self {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..Self::default()
+        }
+    }
+
+    /// Limits the search to a wall-clock budget.
+    pub fn time(time: Duration) -> Self {
+        SearchLimits {
+            time: Some(time),
+            ..Self::default()
+        }
+    }
+
+    /// The depth to search to: `depth` when set, as deep as the node or time
+    /// limit allows when one of those is set, and `default` otherwise.
+    pub(crate) fn max_depth(&self, default: usize) -> usize {
+        match self.depth {
+            Some(depth) => depth,
+            None if self.nodes.is_some() || self.time.is_some() => usize::MAX,
+            None => default,
+        }
+    }
+}
+
+/// A monotonic time source for time-limited searches.
+///
+/// `std::time::Instant` is unavailable on `wasm32-unknown-unknown`, so callers on
+/// that target provide their own clock, e.g. one backed by `Date.now()`.
+pub trait Clock {
+    /// Time elapsed since an arbitrary fixed point.
+    fn now(&self) -> Duration;
+}
+
+/// A `Clock` backed by `std::time::Instant`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: std::time::Instant,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// How many nodes to visit between clock reads.
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+/// Counts the nodes of a search against the node and time parts of its
+/// `SearchLimits`.
+///
+/// The budget is only enforced once `allow_abort` has been called, so that an
+/// iterative deepening search always finishes its first iteration.
+pub(crate) struct Budget<'a> {
+    clock: Option<&'a dyn Clock>,
+    deadline: Option<Duration>,
+    node_limit: Option<u64>,
+    nodes: u64,
+    can_abort: bool,
+    aborted: bool,
+}
+
+impl<'a> Budget<'a> {
+    /// A budget that never runs out.
+    pub(crate) fn unlimited() -> Self {
+        Budget {
+            clock: None,
+            deadline: None,
+            node_limit: None,
+            nodes: 0,
+            can_abort: false,
+            aborted: false,
+        }
+    }
+
+    /// A budget of `nodes` nodes.
+    pub(crate) fn nodes(nodes: u64) -> Self {
+        Budget {
+            node_limit: Some(nodes),
+            ..Self::unlimited()
+        }
+    }
+
+    /// A budget for the node and time limits, with time measured by `clock`.
+    pub(crate) fn new(limits: &SearchLimits, clock: &'a dyn Clock) -> Self {
+        Budget {
+            clock: Some(clock),
+            deadline: limits.time.map(|time| clock.now() + time),
+            node_limit: limits.nodes,
+            ..Self::unlimited()
+        }
+    }
+
+    /// Lets the search be cut short from now on.
+    pub(crate) fn allow_abort(&mut self) {
+        self.can_abort = true;
+    }
+
+    /// Counts a visited node and returns true once the budget is spent.
+    pub(crate) fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if !self.can_abort {
+            return false;
+        }
+        if self.node_limit.is_some_and(|limit| self.nodes >= limit) {
+            self.aborted = true;
+        }
+        if let (Some(clock), Some(deadline)) = (self.clock, self.deadline) {
+            if self.nodes.is_multiple_of(CLOCK_CHECK_INTERVAL) && clock.now() >= deadline {
+                self.aborted = true;
+            }
+        }
+        self.aborted
+    }
+
+    /// Returns true if the search was cut short.
+    pub(crate) fn aborted(&self) -> bool {
+        self.aborted
+    }
+}
@@ -0,0 +1,328 @@
+// core/src/mcts.rs
+
+use crate::position::Position;
+use crate::random::{self, GameRng};
+use crate::{Clock, Color, ConnectFour, Coordinate, GameState, SearchLimits, Strategy};
+use std::time::Duration;
+
+/// A Monte Carlo tree search opponent using the UCT selection rule.
+///
+/// Each playout walks down the tree picking the child with the best upper
+/// confidence bound, adds one new node, and finishes the game with random moves,
+/// taking an immediate win whenever one is available. The most visited move is
+/// played. Boards that do not fit in a `Position` fall back to
+/// `Quality::Best`'s alpha-beta search.
+///
+/// Unless `with_seed` is used, each search draws its random numbers from the
+/// game's generator, so games built with `ConnectFourConfig::seed` repeat.
+#[derive(Debug, Clone)]
+pub struct MctsStrategy {
+    playouts: u64,
+    exploration: f64,
+    rng: GameRng,
+    seeded: bool,
+}
+
+/// A node of the search tree. `reward` is the total result of the playouts
+/// through it for the player who made `col`: 1 for a win, 0.5 for a draw.
+struct Node {
+    col: Coordinate,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Coordinate>,
+    visits: f64,
+    reward: f64,
+    winner: Option<Color>,
+    terminal: bool,
+}
+
+impl MctsStrategy {
+    /// Default number of playouts per move.
+    pub const DEFAULT_PLAYOUTS: u64 = 10_000;
+    /// Default exploration constant, the theoretical value for rewards in [0, 1].
+    pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+    /// Creates a search with the given number of playouts per move and
+    /// exploration constant. Higher constants spread playouts more evenly.
+    pub fn new(playouts: u64, exploration: f64) -> Self {
+        MctsStrategy {
+            playouts: playouts.max(1),
+            exploration,
+            // Replaced by one forked from the game's before each search
+            rng: random::seeded(0),
+            seeded: false,
+        }
+    }
+
+    /// Replaces the random number generator with one seeded from `seed`, so
+    /// the same position always gets the same move, on every platform.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = random::seeded(seed);
+        self.seeded = true;
+        self
+    }
+
+    /// Number of playouts per move.
+    pub fn playouts(&self) -> u64 {
+        self.playouts
+    }
+
+    /// Exploration constant of the UCT rule.
+    pub fn exploration(&self) -> f64 {
+        self.exploration
+    }
+
+    /// Picks a move within the limits instead of the configured playout count.
+    ///
+    /// `limits.nodes` caps the number of playouts and `limits.time` the time
+    /// spent, measured with `clock`; `limits.depth` is ignored. Without either
+    /// limit the configured playout count is used. At least one playout is
+    /// always run.
+    pub fn choose_move_with_limits(
+        &mut self,
+        game: &ConnectFour,
+        state: &GameState,
+        limits: SearchLimits,
+        clock: &dyn Clock,
+    ) -> Coordinate {
+        let playouts = match (limits.nodes, limits.time) {
+            (Some(nodes), _) => nodes,
+            (None, Some(_)) => u64::MAX,
+            (None, None) => self.playouts,
+        };
+        let deadline = limits.time.map(|time| (clock, clock.now() + time));
+        self.search(game, state, playouts, deadline)
+    }
+
+    fn search(
+        &mut self,
+        game: &ConnectFour,
+        state: &GameState,
+        playouts: u64,
+        deadline: Option<(&dyn Clock, Duration)>,
+    ) -> Coordinate {
+        if !self.seeded {
+            self.rng = game.fork_rng();
+        }
+        let Some(root) = Position::from_state(game, state) else {
+            return game.get_best_move(state);
+        };
+        let moves = playable(&root);
+        if moves.is_empty() {
+            return game.get_best_move(state);
+        }
+        if let Some(&col) = moves.iter().find(|&&col| root.is_winning_move(col)) {
+            return col;
+        }
+
+        let mut tree = vec![Node {
+            col: 0,
+            parent: None,
+            children: Vec::new(),
+            untried: moves,
+            visits: 0.0,
+            reward: 0.0,
+            winner: None,
+            terminal: false,
+        }];
+        for playout in 0..playouts.max(1) {
+            if playout > 0 && deadline.is_some_and(|(clock, end)| clock.now() >= end) {
+                break;
+            }
+            self.playout(&mut tree, root);
+        }
+
+        let children = &tree[0].children;
+        children
+            .iter()
+            .max_by(|&&a, &&b| tree[a].visits.total_cmp(&tree[b].visits))
+            .map(|&child| tree[child].col)
+            .unwrap_or_else(|| game.get_best_move(state))
+    }
+
+    fn playout(&mut self, tree: &mut Vec<Node>, mut position: Position) {
+        // Selection
+        let mut node = 0;
+        while tree[node].untried.is_empty() && !tree[node].terminal {
+            node = self.select(tree, node);
+            position.play(tree[node].col);
+        }
+
+        // Expansion
+        if !tree[node].terminal {
+            let index = random::index(&mut self.rng, tree[node].untried.len());
+            let col = tree[node].untried.swap_remove(index);
+            let mover = position.current_player();
+            let wins = position.is_winning_move(col);
+            position.play(col);
+            let terminal = wins || position.is_full();
+            tree.push(Node {
+                col,
+                parent: Some(node),
+                children: Vec::new(),
+                untried: if terminal {
+                    Vec::new()
+                } else {
+                    playable(&position)
+                },
+                visits: 0.0,
+                reward: 0.0,
+                winner: wins.then_some(mover),
+                terminal,
+            });
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            node = child;
+        }
+
+        // Simulation
+        let winner = if tree[node].terminal {
+            tree[node].winner
+        } else {
+            self.simulate(position)
+        };
+
+        // Backpropagation, starting with the player who moved into the new node
+        let mut mover = position.current_player().opponent();
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut tree[index];
+            node.visits += 1.0;
+            node.reward += match winner {
+                Some(color) if color == mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            mover = mover.opponent();
+            current = node.parent;
+        }
+    }
+
+    /// Picks the child with the highest upper confidence bound.
+    fn select(&self, tree: &[Node], node: usize) -> usize {
+        let log_visits = tree[node].visits.ln();
+        let uct = |child: usize| {
+            let child = &tree[child];
+            child.reward / child.visits + self.exploration * (log_visits / child.visits).sqrt()
+        };
+        tree[node]
+            .children
+            .iter()
+            .copied()
+            .max_by(|&a, &b| uct(a).total_cmp(&uct(b)))
+            .expect("expanded nodes have children")
+    }
+
+    /// Plays random moves until the game ends, taking immediate wins.
+    fn simulate(&mut self, mut position: Position) -> Option<Color> {
+        loop {
+            let moves = playable(&position);
+            if let Some(&col) = moves.iter().find(|&&col| position.is_winning_move(col)) {
+                let winner = position.current_player();
+                position.play(col);
+                return Some(winner);
+            }
+            if moves.is_empty() {
+                return None;
+            }
+            position.play(moves[random::index(&mut self.rng, moves.len())]);
+        }
+    }
+}
+
+impl Default for MctsStrategy {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PLAYOUTS, Self::DEFAULT_EXPLORATION)
+    }
+}
+
+impl Strategy for MctsStrategy {
+    fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate {
+        self.search(game, state, self.playouts, None)
+    }
+}
+
+fn playable(position: &Position) -> Vec<Coordinate> {
+    (0..position.width())
+        .filter(|&col| position.can_play(col))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConnectFourConfig;
+
+    #[test]
+    fn test_takes_immediate_win() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("121212").unwrap();
+        let mut mcts = MctsStrategy::new(10, 1.0).with_seed(1);
+        assert_eq!(mcts.choose_move(&game, &state), 0);
+    }
+
+    #[test]
+    fn test_blocks_open_three() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("17273").unwrap();
+        let mut mcts = MctsStrategy::new(2_000, 1.0).with_seed(7);
+        assert_eq!(mcts.choose_move(&game, &state), 3);
+    }
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("44").unwrap();
+        let first = MctsStrategy::new(500, 1.4)
+            .with_seed(3)
+            .choose_move(&game, &state);
+        let second = MctsStrategy::new(500, 1.4)
+            .with_seed(3)
+            .choose_move(&game, &state);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_follows_game_seed() {
+        let play = |seed| {
+            let game = ConnectFourConfig::new().seed(seed).build().unwrap();
+            let mut mcts = MctsStrategy::new(20, 1.4);
+            let mut state = game.create_initial_state();
+            for _ in 0..8 {
+                state = game.place_piece(&state, mcts.choose_move(&game, &state));
+            }
+            state.to_move_string()
+        };
+        assert_eq!(play(9), play(9));
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let game = ConnectFour::new();
+        let state = game.create_initial_state();
+        let mut mcts = MctsStrategy::default().with_seed(5);
+        let col = mcts.choose_move_with_limits(&game, &state, SearchLimits::nodes(1), &NoClock);
+        assert!(col < game.num_columns());
+    }
+
+    #[test]
+    fn test_default_limits_use_playouts() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("44").unwrap();
+        let limited = MctsStrategy::new(500, 1.4)
+            .with_seed(3)
+            .choose_move_with_limits(&game, &state, SearchLimits::default(), &NoClock);
+        let unlimited = MctsStrategy::new(500, 1.4)
+            .with_seed(3)
+            .choose_move(&game, &state);
+        assert_eq!(limited, unlimited);
+    }
+
+    struct NoClock;
+
+    impl Clock for NoClock {
+        fn now(&self) -> Duration {
+            Duration::ZERO
+        }
+    }
+}
@@ -0,0 +1,254 @@
+// core/src/mnk.rs
+
+use crate::{Cell, ConnectFour, Coordinate, GameState, Move, MoveError, Variant};
+
+impl ConnectFour {
+    /// Places the current player's piece in any empty cell, with row 0 at the
+    /// top. Only allowed in `Variant::Mnk`, which has no gravity.
+    pub fn try_place_at(
+        &self,
+        state: &GameState,
+        column: Coordinate,
+        row: usize,
+    ) -> Result<GameState, MoveError> {
+        if self.variant != Variant::Mnk {
+            return Err(MoveError::PlaceNotAllowed);
+        }
+        if state.is_game_over {
+            return Err(MoveError::GameOver);
+        }
+        if column >= self.num_columns {
+            return Err(MoveError::ColumnOutOfRange {
+                column,
+                num_columns: self.num_columns,
+            });
+        }
+        if row >= self.num_rows {
+            return Err(MoveError::RowOutOfRange {
+                row,
+                num_rows: self.num_rows,
+            });
+        }
+        if state.board[column][row] != Cell::Empty {
+            return Err(MoveError::CellOccupied { column, row });
+        }
+
+        let mut new_board = state.board.clone();
+        new_board[column][row] = Cell::Filled(state.current_player);
+
+        Ok(self.finish_move(state, new_board, Move::Place { column, row }))
+    }
+
+    /// Every empty cell of an m,n,k game, column by column.
+    pub(crate) fn mnk_moves(&self, state: &GameState) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for (column, cells) in state.board.iter().enumerate() {
+            for (row, &cell) in cells.iter().enumerate() {
+                if cell == Cell::Empty {
+                    moves.push(Move::Place { column, row });
+                }
+            }
+        }
+        moves
+    }
+
+    /// The placements worth searching in an m,n,k game: empty cells next to a
+    /// piece, or the center of an empty board. A line is always completed next
+    /// to one of its other pieces, so no immediate win is left out.
+    pub(crate) fn mnk_candidate_moves(&self, state: &GameState) -> Vec<Move> {
+        let moves = self.mnk_moves(state);
+        let next_to_piece = |&mv: &Move| {
+            let Move::Place { column, row } = mv else {
+                return false;
+            };
+            (-1..=1).any(|dc: isize| {
+                (-1..=1).any(|dr: isize| {
+                    self.neighbor(column, row, dc, dr)
+                        .is_some_and(|(c, r)| state.board[c][r] != Cell::Empty)
+                })
+            })
+        };
+        let candidates: Vec<Move> = moves.iter().copied().filter(next_to_piece).collect();
+        if !candidates.is_empty() || moves.is_empty() {
+            return candidates;
+        }
+        let center = Move::Place {
+            column: self.num_columns / 2,
+            row: self.num_rows / 2,
+        };
+        if moves.contains(&center) {
+            vec![center]
+        } else {
+            moves
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ConnectFourConfig, NotationError, Quality, SearchLimits};
+
+    #[test]
+    fn test_tic_tac_toe() {
+        let game = ConnectFourConfig::new()
+            .num_columns(3)
+            .num_rows(3)
+            .winning_length(3)
+            .variant(Variant::Mnk)
+            .max_depth(2)
+            .seed(11)
+            .build()
+            .unwrap();
+        let state = game.create_initial_state();
+        assert_eq!(game.legal_moves(&state).len(), 9);
+        assert_eq!(
+            game.try_play(&state, Move::Drop(0)),
+            Err(MoveError::DropNotAllowed)
+        );
+        assert_eq!(
+            game.try_place_at(&state, 1, 3),
+            Err(MoveError::RowOutOfRange {
+                row: 3,
+                num_rows: 3
+            })
+        );
+
+        // Red takes the diagonal from the top left
+        let state = game.state_from_moves("11122213").unwrap();
+        assert_eq!(
+            game.try_place_at(&state, 0, 0),
+            Err(MoveError::CellOccupied { column: 0, row: 0 })
+        );
+        let won = game.try_place_at(&state, 2, 2).unwrap();
+        assert_eq!(won.winner, Some(Color::Red));
+        assert_eq!(won.to_move_string(), "1112221333");
+        assert_eq!(
+            serde_json::to_string(&won.moves[4]).unwrap(),
+            r#"{"column":2,"row":2}"#
+        );
+        assert_eq!(
+            game.state_from_moves("221"),
+            Err(NotationError::InvalidCharacter {
+                index: 2,
+                character: '1'
+            })
+        );
+
+        let undone = game.undo(&won).unwrap();
+        assert_eq!(undone.board, state.board);
+        assert_eq!(game.redo(&undone).unwrap(), won);
+
+        // A full board without a line is a draw
+        let drawn = game.state_from_moves("112131221232231333").unwrap();
+        assert!(drawn.is_game_over);
+        assert_eq!(drawn.winner, None);
+        assert_eq!(drawn.to_fen(), "ryr/ryy/yrr y");
+        assert_eq!(
+            game.state_from_fen(&drawn.to_fen()).unwrap().board,
+            drawn.board
+        );
+    }
+
+    #[test]
+    fn test_tic_tac_toe_ai_blocks() {
+        let game = ConnectFourConfig::new()
+            .num_columns(3)
+            .num_rows(3)
+            .winning_length(3)
+            .variant(Variant::Mnk)
+            .max_depth(2)
+            .seed(11)
+            .build()
+            .unwrap();
+        // Yellow must stop Red's top row
+        let state = game.state_from_moves("112221").unwrap();
+        for quality in [Quality::Medium, Quality::Best] {
+            assert_eq!(
+                game.get_variant_move(&state, quality),
+                Move::Place { column: 2, row: 0 }
+            );
+        }
+    }
+
+    #[test]
+    fn test_gomoku() {
+        let game = ConnectFourConfig::new()
+            .num_columns(15)
+            .num_rows(15)
+            .winning_length(5)
+            .variant(Variant::Mnk)
+            .max_depth(2)
+            .seed(11)
+            .build()
+            .unwrap();
+        assert_eq!(
+            game.get_variant_move(&game.create_initial_state(), Quality::Best),
+            Move::Place { column: 7, row: 7 }
+        );
+
+        // Red has an open four in the middle row and wins at either end
+        let state = game.state_from_moves("6811782188319812").unwrap();
+        for quality in [Quality::Medium, Quality::Best] {
+            let mv = game.get_variant_move(&state, quality);
+            assert_eq!(game.try_play(&state, mv).unwrap().winner, Some(Color::Red));
+        }
+    }
+
+    #[test]
+    fn test_column_analysis_is_empty() {
+        let game = ConnectFourConfig::new()
+            .num_columns(3)
+            .num_rows(3)
+            .winning_length(3)
+            .variant(Variant::Mnk)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("22").unwrap();
+        assert!(game.analyze(&state, SearchLimits::default()).is_empty());
+        assert!(game.tactics(&state).forced_blocks.is_empty());
+    }
+
+    #[test]
+    fn test_computer_move_places() {
+        let game = ConnectFourConfig::new()
+            .num_columns(3)
+            .num_rows(3)
+            .winning_length(3)
+            .variant(Variant::Mnk)
+            .max_depth(2)
+            .seed(11)
+            .build()
+            .unwrap();
+        // Yellow must stop Red's top row
+        let state = game.state_from_moves("112221").unwrap();
+        assert_eq!(
+            game.get_computer_move(&state, Quality::Best),
+            Move::Place { column: 2, row: 0 }
+        );
+        let mv = game.get_computer_move_with_limits(
+            &state,
+            SearchLimits::nodes(1),
+            &crate::SystemClock::new(),
+        );
+        assert!(matches!(mv, Move::Place { .. }));
+    }
+
+    #[test]
+    fn test_gomoku_default_depth() {
+        let game = ConnectFourConfig::new()
+            .num_columns(15)
+            .num_rows(15)
+            .winning_length(5)
+            .variant(Variant::Mnk)
+            .build()
+            .unwrap();
+        // The search stops at its node budget instead of trying every reply
+        // five moves deep, and still sees that Red's four must be closed
+        let state = game.state_from_moves("584868aa78bb88").unwrap();
+        assert_eq!(
+            game.get_variant_move(&state, Quality::Best),
+            Move::Place { column: 8, row: 7 }
+        );
+    }
+}
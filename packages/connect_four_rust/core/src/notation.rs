@@ -0,0 +1,733 @@
+// core/src/notation.rs
+
+use crate::{
+    Cell, Color, ConnectFour, Coordinate, GameState, Move, MoveError, PopTenPhase, PopTenState,
+    Variant, MAX_PLAYERS, POP_TEN_TARGET,
+};
+use std::fmt;
+
+/// Marks a pop in move notation.
+const POP_PREFIX: char = '^';
+
+/// Board and side-to-move letters of each color, in `Color::ALL` order.
+const PIECE_CHARS: [char; MAX_PLAYERS] = ['r', 'y', 'g', 'b'];
+
+/// Why a move sequence could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotationError {
+    /// The character is not a column number. `index` counts characters from 0.
+    InvalidCharacter { index: usize, character: char },
+    /// The move at `index` cannot be played in the position reached so far.
+    IllegalMove {
+        index: usize,
+        column: Coordinate,
+        error: MoveError,
+    },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::InvalidCharacter { index, character } => write!(
+                f,
+                "invalid character {:?} at position {}",
+                character,
+                index + 1
+            ),
+            NotationError::IllegalMove {
+                index,
+                column,
+                error,
+            } => write!(
+                f,
+                "move {} (column {}) is illegal: {}",
+                index + 1,
+                column_char(*column).unwrap_or('?'),
+                error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+/// Why a board string could not be parsed by `ConnectFour::state_from_fen`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// The string has no side-to-move field.
+    MissingSideToMove,
+    /// The side to move is not the letter of a player in the game.
+    InvalidSideToMove(String),
+    /// The dimensions field is not of the form `<columns>x<rows>`.
+    InvalidDimensions(String),
+    /// The Pop 10 field is not of the form `<phase>:<red>:<yellow>`.
+    InvalidPopTen(String),
+    /// The string has a field it cannot have: a repeated one, or a Pop 10
+    /// field in another variant.
+    UnexpectedField(String),
+    /// The dimensions do not match the game.
+    DimensionMismatch { num_columns: usize, num_rows: usize },
+    /// The number of rows does not match the game.
+    WrongRowCount { expected: usize, found: usize },
+    /// A row does not have one entry per column. Rows count from 0 at the top.
+    WrongRowLength {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A row contains something other than the letter of a player in the game
+    /// or a count of empty cells.
+    InvalidCharacter { row: usize, character: char },
+    /// A piece has an empty cell below it.
+    FloatingPiece { column: Coordinate, row: usize },
+    /// The side to move does not match the number of pieces of each color.
+    WrongSideToMove,
+    /// More than one player has a winning line.
+    BothPlayersWon,
+    /// The Pop 10 phase does not fit the board: the setup phase ends when the
+    /// board is full, and a piece waiting to be returned needs an empty cell.
+    WrongPhase,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingSideToMove => write!(f, "missing side to move"),
+            FenError::InvalidSideToMove(field) => write!(f, "invalid side to move {:?}", field),
+            FenError::InvalidDimensions(field) => write!(f, "invalid dimensions {:?}", field),
+            FenError::InvalidPopTen(field) => write!(f, "invalid Pop 10 field {:?}", field),
+            FenError::UnexpectedField(field) => write!(f, "unexpected field {:?}", field),
+            FenError::DimensionMismatch {
+                num_columns,
+                num_rows,
+            } => write!(
+                f,
+                "a {}x{} board does not match the game",
+                num_columns, num_rows
+            ),
+            FenError::WrongRowCount { expected, found } => {
+                write!(f, "expected {} rows, found {}", expected, found)
+            }
+            FenError::WrongRowLength {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} cells instead of {}",
+                row + 1,
+                found,
+                expected
+            ),
+            FenError::InvalidCharacter { row, character } => {
+                write!(f, "invalid character {:?} in row {}", character, row + 1)
+            }
+            FenError::FloatingPiece { column, row } => write!(
+                f,
+                "the piece in row {} of column {} has nothing below it",
+                row + 1,
+                column + 1
+            ),
+            FenError::WrongSideToMove => {
+                write!(f, "the side to move does not match the number of pieces")
+            }
+            FenError::BothPlayersWon => write!(f, "more than one player has a winning line"),
+            FenError::WrongPhase => write!(f, "the Pop 10 phase does not match the board"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl ConnectFour {
+    /// Plays a move sequence from the initial position, e.g. `"4453627"`.
+    ///
+    /// Each character is a 1-indexed column. Columns past 9 continue with the
+    /// letters `a` to `z`, so boards of up to 35 columns can be written down.
+    /// A `^` before a column pops instead of dropping, e.g. `"1112^1"`. In
+    /// `Variant::Mnk` each move is a column followed by a row, counting rows
+    /// from 1 at the top in the same way, e.g. `"2213"`.
+    pub fn state_from_moves(&self, moves: &str) -> Result<GameState, NotationError> {
+        let mut state = self.create_initial_state();
+        let mut pop = false;
+        let mut pending_column = None;
+        for (index, character) in moves.chars().enumerate() {
+            if character == POP_PREFIX && !pop && pending_column.is_none() {
+                pop = true;
+                continue;
+            }
+            let coordinate = parse_column(character)
+                .ok_or(NotationError::InvalidCharacter { index, character })?;
+            let (column, mv) = match pending_column.take() {
+                Some(column) => (
+                    column,
+                    Move::Place {
+                        column,
+                        row: coordinate,
+                    },
+                ),
+                None if self.variant == Variant::Mnk && !pop => {
+                    pending_column = Some(coordinate);
+                    continue;
+                }
+                None if pop => (coordinate, Move::Pop { column: coordinate }),
+                None => (coordinate, Move::Drop(coordinate)),
+            };
+            state = self
+                .try_play(&state, mv)
+                .map_err(|error| NotationError::IllegalMove {
+                    index,
+                    column,
+                    error,
+                })?;
+            pop = false;
+        }
+        // A pop prefix or a column without its row is left over
+        if let (true, Some(character)) = (pop || pending_column.is_some(), moves.chars().last()) {
+            return Err(NotationError::InvalidCharacter {
+                index: moves.chars().count() - 1,
+                character,
+            });
+        }
+        Ok(state)
+    }
+
+    /// Parses a board written by `GameState::to_fen`.
+    ///
+    /// The rows are listed from top to bottom and separated by `/`. Each row
+    /// holds `r` for red, `y` for yellow, `g` for green, `b` for blue and
+    /// numbers for runs of empty cells, so the empty standard board is
+    /// `7/7/7/7/7/7 r`. The second field is the side to move, and an optional
+    /// field such as `7x6` gives the dimensions, which must match the game.
+    /// Pop 10 boards may add a field with the phase (`setup`, `pop` or
+    /// `return`) and the pieces red and yellow have collected, e.g. `pop:3:1`.
+    /// Without it the phase is worked out from the board and no pieces have
+    /// been collected.
+    ///
+    /// The board must be reachable by dropping pieces in turn, or by placing
+    /// them in `Variant::Mnk`; with pops the side to move is not checked
+    /// against the piece counts. The winner is
+    /// worked out from the board, and the returned state has no move history.
+    pub fn state_from_fen(&self, fen: &str) -> Result<GameState, FenError> {
+        let mut fields = fen.split_whitespace();
+        let rows: Vec<&str> = fields.next().unwrap_or("").split('/').collect();
+        let current_player = match fields.next() {
+            Some(field) => {
+                let mut chars = field.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(character), None) => parse_piece(character, self.num_players),
+                    _ => None,
+                }
+                .ok_or_else(|| FenError::InvalidSideToMove(field.to_string()))?
+            }
+            None => return Err(FenError::MissingSideToMove),
+        };
+        let mut dimensions = None;
+        let mut progress = None;
+        for field in fields {
+            if field.contains(':') && self.variant == Variant::PopTen && progress.is_none() {
+                progress = Some(
+                    parse_pop_ten(field)
+                        .ok_or_else(|| FenError::InvalidPopTen(field.to_string()))?,
+                );
+            } else if !field.contains(':') && dimensions.is_none() {
+                dimensions = Some(
+                    parse_dimensions(field)
+                        .ok_or_else(|| FenError::InvalidDimensions(field.to_string()))?,
+                );
+            } else {
+                return Err(FenError::UnexpectedField(field.to_string()));
+            }
+        }
+        if let Some((num_columns, num_rows)) = dimensions {
+            if num_columns != self.num_columns || num_rows != self.num_rows {
+                return Err(FenError::DimensionMismatch {
+                    num_columns,
+                    num_rows,
+                });
+            }
+        }
+        if rows.len() != self.num_rows {
+            return Err(FenError::WrongRowCount {
+                expected: self.num_rows,
+                found: rows.len(),
+            });
+        }
+
+        let mut board = self.create_board();
+        for (row, text) in rows.iter().enumerate() {
+            let cells = parse_row(text, self.num_players)
+                .map_err(|character| FenError::InvalidCharacter { row, character })?;
+            if cells.len() != self.num_columns {
+                return Err(FenError::WrongRowLength {
+                    row,
+                    expected: self.num_columns,
+                    found: cells.len(),
+                });
+            }
+            for (column, cell) in cells.into_iter().enumerate() {
+                board[column][row] = cell;
+            }
+        }
+
+        let mut counts = [0usize; MAX_PLAYERS];
+        for (column, cells) in board.iter().enumerate() {
+            for (row, cell) in cells.iter().enumerate() {
+                match cell {
+                    Cell::Filled(color) => counts[color.index()] += 1,
+                    Cell::Empty => continue,
+                }
+                if self.variant != Variant::Mnk && cells.get(row + 1) == Some(&Cell::Empty) {
+                    return Err(FenError::FloatingPiece { column, row });
+                }
+            }
+        }
+        if matches!(
+            self.variant,
+            Variant::Standard | Variant::Misere | Variant::Mnk
+        ) {
+            // Players earlier in turn order have one piece more until the round ends
+            let num_players = self.num_players;
+            let total: usize = counts.iter().sum();
+            let balanced = (0..num_players)
+                .all(|i| counts[i] == total / num_players + usize::from(i < total % num_players));
+            if !balanced || current_player != Color::ALL[total % num_players] {
+                return Err(FenError::WrongSideToMove);
+            }
+        }
+
+        // Lines do not end a Pop 10 game; collecting enough pieces does
+        let full = self.is_board_full(&board);
+        let pop_ten = match (self.variant, progress) {
+            (Variant::PopTen, Some(progress)) => {
+                let fits = match progress.phase {
+                    PopTenPhase::Setup | PopTenPhase::Return => !full,
+                    PopTenPhase::Pop => true,
+                };
+                if !fits {
+                    return Err(FenError::WrongPhase);
+                }
+                Some(progress)
+            }
+            (Variant::PopTen, None) => Some(PopTenState {
+                phase: if full {
+                    PopTenPhase::Pop
+                } else {
+                    PopTenPhase::Setup
+                },
+                ..PopTenState::default()
+            }),
+            _ => None,
+        };
+        let mut winners = Color::ALL[..self.num_players]
+            .iter()
+            .copied()
+            .filter(|&player| match &pop_ten {
+                Some(progress) => progress.collected(player) >= POP_TEN_TARGET,
+                None => self.check_winner(&board, player),
+            });
+        let mut winner = winners.next();
+        if winners.next().is_some() {
+            return Err(FenError::BothPlayersWon);
+        }
+        if self.variant == Variant::Misere {
+            winner = winner.map(Color::opponent);
+        }
+        let mut state = GameState {
+            board,
+            current_player,
+            winner,
+            is_game_over: winner.is_some(),
+            moves: Vec::new(),
+            undone_moves: Vec::new(),
+            pop_ten,
+        };
+        state.is_game_over = winner.is_some() || self.is_draw(&state);
+        Ok(state)
+    }
+}
+
+impl GameState {
+    /// Formats the board and side to move in the notation read by
+    /// `ConnectFour::state_from_fen`. Pop 10 states add their phase and
+    /// collections.
+    pub fn to_fen(&self) -> String {
+        let num_rows = self.board.first().map_or(0, |column| column.len());
+        let rows: Vec<String> = (0..num_rows)
+            .map(|row| {
+                let mut text = String::new();
+                let mut empty = 0;
+                for column in &self.board {
+                    let piece = match column[row] {
+                        Cell::Empty => {
+                            empty += 1;
+                            continue;
+                        }
+                        Cell::Filled(color) => PIECE_CHARS[color.index()],
+                    };
+                    if empty > 0 {
+                        text.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    text.push(piece);
+                }
+                if empty > 0 {
+                    text.push_str(&empty.to_string());
+                }
+                text
+            })
+            .collect();
+        let side = PIECE_CHARS[self.current_player.index()];
+        match &self.pop_ten {
+            Some(progress) => format!(
+                "{} {} {}:{}:{}",
+                rows.join("/"),
+                side,
+                phase_name(progress.phase),
+                progress.red_collected,
+                progress.yellow_collected
+            ),
+            None => format!("{} {}", rows.join("/"), side),
+        }
+    }
+
+    /// Like `to_fen`, followed by the board dimensions, e.g. `7x6`.
+    pub fn to_fen_with_dimensions(&self) -> String {
+        let num_rows = self.board.first().map_or(0, |column| column.len());
+        format!("{} {}x{}", self.to_fen(), self.board.len(), num_rows)
+    }
+
+    /// Formats the moves played so far in the notation read by
+    /// `ConnectFour::state_from_moves`.
+    ///
+    /// States that were not built move by move, e.g. by `Position::to_state`,
+    /// have no recorded moves and format as an empty string.
+    pub fn to_move_string(&self) -> String {
+        let mut text = String::new();
+        for &mv in &self.moves {
+            if let Move::Pop { .. } = mv {
+                text.push(POP_PREFIX);
+            }
+            text.push(column_char(mv.column()).unwrap_or('?'));
+            if let Move::Place { row, .. } = mv {
+                text.push(column_char(row).unwrap_or('?'));
+            }
+        }
+        text
+    }
+}
+
+/// Expands one row, returning the offending character on failure.
+fn parse_row(text: &str, num_players: usize) -> Result<Vec<Cell>, char> {
+    let mut cells = Vec::new();
+    let mut empty = 0;
+    for character in text.chars() {
+        if let Some(digit) = character.to_digit(10) {
+            if digit == 0 && empty == 0 {
+                return Err(character);
+            }
+            empty = empty * 10 + digit as usize;
+            if empty > crate::MAX_DIMENSION {
+                return Err(character);
+            }
+            continue;
+        }
+        cells.extend(std::iter::repeat_n(Cell::Empty, empty));
+        empty = 0;
+        let color = parse_piece(character, num_players).ok_or(character)?;
+        cells.push(Cell::Filled(color));
+    }
+    cells.extend(std::iter::repeat_n(Cell::Empty, empty));
+    Ok(cells)
+}
+
+/// The color written as `character`, if it plays in a game of `num_players`.
+fn parse_piece(character: char, num_players: usize) -> Option<Color> {
+    PIECE_CHARS[..num_players]
+        .iter()
+        .position(|&piece| piece == character)
+        .map(|index| Color::ALL[index])
+}
+
+fn parse_dimensions(field: &str) -> Option<(usize, usize)> {
+    let (columns, rows) = field.split_once('x')?;
+    Some((columns.parse().ok()?, rows.parse().ok()?))
+}
+
+/// Reads a Pop 10 field such as `pop:3:1`.
+fn parse_pop_ten(field: &str) -> Option<PopTenState> {
+    let mut parts = field.split(':');
+    let name = parts.next()?;
+    let phase = [PopTenPhase::Setup, PopTenPhase::Pop, PopTenPhase::Return]
+        .into_iter()
+        .find(|&phase| phase_name(phase) == name)?;
+    let red_collected = parts.next()?.parse().ok()?;
+    let yellow_collected = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(PopTenState {
+        phase,
+        red_collected,
+        yellow_collected,
+        ..PopTenState::default()
+    })
+}
+
+fn phase_name(phase: PopTenPhase) -> &'static str {
+    match phase {
+        PopTenPhase::Setup => "setup",
+        PopTenPhase::Pop => "pop",
+        PopTenPhase::Return => "return",
+    }
+}
+
+fn parse_column(character: char) -> Option<Coordinate> {
+    match character.to_digit(36) {
+        Some(0) | None => None,
+        Some(digit) => Some(digit as Coordinate - 1),
+    }
+}
+
+fn column_char(column: Coordinate) -> Option<char> {
+    std::char::from_digit(column as u32 + 1, 36)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ConnectFourConfig};
+
+    #[test]
+    fn test_round_trip() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("4453627").unwrap();
+        assert_eq!(state.moves, [3, 3, 4, 2, 5, 1, 6].map(Move::Drop));
+        assert_eq!(state.current_player, Color::Yellow);
+        assert_eq!(state.to_move_string(), "4453627");
+
+        let mut expected = game.create_initial_state();
+        for &col in &[3, 3, 4, 2, 5, 1, 6] {
+            expected = game.place_piece(&expected, col);
+        }
+        assert_eq!(state, expected);
+        assert_eq!(game.state_from_moves(""), Ok(game.create_initial_state()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let game = ConnectFour::new();
+        assert_eq!(
+            game.state_from_moves("44-"),
+            Err(NotationError::InvalidCharacter {
+                index: 2,
+                character: '-'
+            })
+        );
+        assert_eq!(
+            game.state_from_moves("408").unwrap_err(),
+            NotationError::InvalidCharacter {
+                index: 1,
+                character: '0'
+            }
+        );
+        assert_eq!(
+            game.state_from_moves("48"),
+            Err(NotationError::IllegalMove {
+                index: 1,
+                column: 7,
+                error: MoveError::ColumnOutOfRange {
+                    column: 7,
+                    num_columns: 7
+                }
+            })
+        );
+        assert_eq!(
+            game.state_from_moves("1111111"),
+            Err(NotationError::IllegalMove {
+                index: 6,
+                column: 0,
+                error: MoveError::ColumnFull { column: 0 }
+            })
+        );
+        assert!(matches!(
+            game.state_from_moves("12121211"),
+            Err(NotationError::IllegalMove {
+                index: 7,
+                error: MoveError::GameOver,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_wide_board() {
+        let game = ConnectFourConfig::new().num_columns(12).build().unwrap();
+        let state = game.state_from_moves("a1C").unwrap();
+        assert_eq!(state.moves, [9, 0, 11].map(Move::Drop));
+        assert_eq!(state.to_move_string(), "a1c");
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        let game = ConnectFour::new();
+        let empty = game.create_initial_state();
+        assert_eq!(empty.to_fen(), "7/7/7/7/7/7 r");
+        assert_eq!(empty.to_fen_with_dimensions(), "7/7/7/7/7/7 r 7x6");
+
+        let state = game.state_from_moves("4453627").unwrap();
+        assert_eq!(state.to_fen(), "7/7/7/7/3y3/1yyrrrr y");
+        let parsed = game
+            .state_from_fen(&state.to_fen_with_dimensions())
+            .unwrap();
+        assert_eq!(parsed.board, state.board);
+        assert_eq!(parsed.winner, Some(Color::Red));
+        assert!(parsed.is_game_over);
+        assert!(parsed.moves.is_empty());
+
+        let wide = ConnectFourConfig::new().num_columns(12).build().unwrap();
+        let state = wide.state_from_moves("1c").unwrap();
+        assert_eq!(state.to_fen(), "12/12/12/12/12/r10y r");
+        assert_eq!(
+            wide.state_from_fen(&state.to_fen()).unwrap().board,
+            state.board
+        );
+    }
+
+    #[test]
+    fn test_fen_errors() {
+        let game = ConnectFour::new();
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/7"),
+            Err(FenError::MissingSideToMove)
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/7 b"),
+            Err(FenError::InvalidSideToMove("b".to_string()))
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/7 r 8x6"),
+            Err(FenError::DimensionMismatch {
+                num_columns: 8,
+                num_rows: 6
+            })
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7 r"),
+            Err(FenError::WrongRowCount {
+                expected: 6,
+                found: 5
+            })
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/6rr r"),
+            Err(FenError::WrongRowLength {
+                row: 5,
+                expected: 7,
+                found: 8
+            })
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/3x3 r"),
+            Err(FenError::InvalidCharacter {
+                row: 5,
+                character: 'x'
+            })
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/3r3/7 y"),
+            Err(FenError::FloatingPiece { column: 3, row: 4 })
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/3r3 r"),
+            Err(FenError::WrongSideToMove)
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/ry5/ry5/ry5/ry5 r"),
+            Err(FenError::BothPlayersWon)
+        );
+    }
+
+    #[test]
+    fn test_fen_pop_ten_phase() {
+        let game = ConnectFourConfig::new()
+            .num_columns(4)
+            .num_rows(2)
+            .variant(Variant::PopTen)
+            .build()
+            .unwrap();
+        // Red keeps the popped piece and has to pop again
+        let state = game.state_from_moves("11223344^1").unwrap();
+        assert_eq!(state.to_fen(), "1yyy/yrrr r pop:1:0");
+        let parsed = game
+            .state_from_fen(&state.to_fen_with_dimensions())
+            .unwrap();
+        assert_eq!(parsed.board, state.board);
+        assert_eq!(parsed.pop_ten.as_ref().unwrap().phase, PopTenPhase::Pop);
+        assert_eq!(parsed.pop_ten.as_ref().unwrap().red_collected, 1);
+        assert_eq!(game.legal_moves(&parsed), game.legal_moves(&state));
+
+        let state = game.state_from_moves("11223344^1^2").unwrap();
+        let parsed = game.state_from_fen(&state.to_fen()).unwrap();
+        assert_eq!(
+            parsed.pop_ten,
+            state.pop_ten.map(|progress| PopTenState {
+                pops: Vec::new(),
+                ..progress
+            })
+        );
+
+        // Collecting enough pieces wins
+        let won = game.state_from_fen("yyyy/rrrr y pop:10:0").unwrap();
+        assert_eq!(won.winner, Some(Color::Red));
+        assert!(won.is_game_over);
+
+        assert_eq!(
+            game.state_from_fen("yyyy/rrrr r setup:0:0"),
+            Err(FenError::WrongPhase)
+        );
+        assert_eq!(
+            game.state_from_fen("yyyy/rrrr r pop:1"),
+            Err(FenError::InvalidPopTen("pop:1".to_string()))
+        );
+        assert_eq!(
+            ConnectFour::new().state_from_fen("7/7/7/7/7/7 r pop:0:0"),
+            Err(FenError::UnexpectedField("pop:0:0".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_fen_with_three_players() {
+        let game = ConnectFourConfig::new().num_players(3).build().unwrap();
+        let state = game.state_from_moves("1234").unwrap();
+        assert_eq!(state.to_fen(), "7/7/7/7/7/rygr3 y");
+        assert_eq!(
+            game.state_from_fen(&state.to_fen()).unwrap().board,
+            state.board
+        );
+
+        // The earlier players have one piece more until the round is complete
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/ry5 r"),
+            Err(FenError::WrongSideToMove)
+        );
+        assert_eq!(
+            game.state_from_fen("7/7/7/7/7/rr5 y"),
+            Err(FenError::WrongSideToMove)
+        );
+        assert!(game.state_from_fen("7/7/7/7/7/ry5 g").is_ok());
+
+        // Green does not play in a two-player game
+        assert_eq!(
+            ConnectFour::new().state_from_fen("7/7/7/7/7/7 g"),
+            Err(FenError::InvalidSideToMove("g".to_string()))
+        );
+        assert_eq!(
+            ConnectFour::new().state_from_fen("7/7/7/7/7/g6 y"),
+            Err(FenError::InvalidCharacter {
+                row: 5,
+                character: 'g'
+            })
+        );
+    }
+}
@@ -0,0 +1,156 @@
+// core/src/perft.rs
+
+use crate::{Board, Color, ConnectFour, GameState, Move, PopTenState, Variant};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// What `ConnectFour::perft` found at one depth.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerftCounts {
+    /// Move sequences of exactly this length, including those whose last move
+    /// ended the game.
+    pub nodes: u64,
+    /// Distinct positions those sequences reach, told apart by board and side
+    /// to move.
+    pub positions: u64,
+    /// Sequences whose last move won the game.
+    pub wins: u64,
+    /// Sequences whose last move drew the game.
+    pub draws: u64,
+}
+
+/// What identifies a position for `perft`. In PopOut the moves played decide
+/// repetitions, so sequences that reach the same board are only expanded
+/// together if they got there the same way.
+#[derive(PartialEq, Eq, Hash)]
+struct PerftKey {
+    board: Board,
+    current_player: Color,
+    pop_ten: Option<PopTenState>,
+    history: Vec<Move>,
+}
+
+impl ConnectFour {
+    /// Enumerates every legal move sequence of up to `depth` moves from `state`
+    /// and counts them at each depth; entry `i` of the result is for `i + 1`
+    /// moves. Finished games are counted where they end and not played on.
+    ///
+    /// Sequences that reach the same position are expanded once and weighted
+    /// by how many there are, so deep counts stay cheap when moves transpose.
+    /// Useful for checking move generation against published position counts.
+    pub fn perft(&self, state: &GameState, depth: usize) -> Vec<PerftCounts> {
+        let mut frontier = HashMap::new();
+        if !state.is_game_over {
+            frontier.insert(self.perft_key(state), (state.clone(), 1));
+        }
+
+        let mut counts = Vec::with_capacity(depth);
+        for _ in 0..depth {
+            let mut ply = PerftCounts::default();
+            let mut next: HashMap<PerftKey, (GameState, u64)> = HashMap::new();
+            for (state, paths) in frontier.into_values() {
+                for mv in self.legal_moves(&state) {
+                    let child = self
+                        .try_play(&state, mv)
+                        .expect("legal moves can be played");
+                    ply.nodes += paths;
+                    if child.winner.is_some() {
+                        ply.wins += paths;
+                    } else if child.is_game_over {
+                        ply.draws += paths;
+                    }
+                    next.entry(self.perft_key(&child)).or_insert((child, 0)).1 += paths;
+                }
+            }
+            ply.positions = next
+                .keys()
+                .map(|key| (&key.board, key.current_player, &key.pop_ten))
+                .collect::<HashSet<_>>()
+                .len() as u64;
+            counts.push(ply);
+
+            next.retain(|_, (state, _)| !state.is_game_over);
+            frontier = next;
+        }
+        counts
+    }
+
+    fn perft_key(&self, state: &GameState) -> PerftKey {
+        PerftKey {
+            board: state.board.clone(),
+            current_player: state.current_player,
+            // Earlier pops are history, not part of the position
+            pop_ten: state.pop_ten.as_ref().map(|progress| PopTenState {
+                pops: Vec::new(),
+                ..progress.clone()
+            }),
+            history: if self.variant == Variant::PopOut {
+                state.moves.clone()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConnectFourConfig;
+
+    #[test]
+    fn test_standard_position_counts() {
+        let game = ConnectFour::new();
+        let counts = game.perft(&game.create_initial_state(), 6);
+        // Positions after each ply of standard connect four (OEIS A212693)
+        let positions: Vec<u64> = counts.iter().map(|ply| ply.positions).collect();
+        assert_eq!(positions, vec![7, 49, 238, 1120, 4263, 16422]);
+        let nodes: Vec<u64> = counts.iter().map(|ply| ply.nodes).collect();
+        assert_eq!(nodes, vec![7, 49, 343, 2401, 16807, 117649]);
+        assert!(counts.iter().all(|ply| ply.wins == 0 && ply.draws == 0));
+    }
+
+    #[test]
+    fn test_popout_positions_ignore_move_order() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::PopOut)
+            .build()
+            .unwrap();
+        let counts = game.perft(&game.create_initial_state(), 3);
+        // On the third move Red can also pop their first piece, which leaves
+        // one of seven boards with a single yellow piece
+        let positions: Vec<u64> = counts.iter().map(|ply| ply.positions).collect();
+        assert_eq!(positions, vec![7, 49, 238 + 7]);
+        let nodes: Vec<u64> = counts.iter().map(|ply| ply.nodes).collect();
+        assert_eq!(nodes, vec![7, 49, 343 + 49]);
+    }
+
+    #[test]
+    fn test_tic_tac_toe_game_tree() {
+        let game = ConnectFourConfig::new()
+            .num_columns(3)
+            .num_rows(3)
+            .winning_length(3)
+            .variant(Variant::Mnk)
+            .build()
+            .unwrap();
+        let counts = game.perft(&game.create_initial_state(), 10);
+        assert_eq!(counts.len(), 10);
+        assert_eq!(counts[9], PerftCounts::default());
+
+        let nodes: Vec<u64> = counts[..9].iter().map(|ply| ply.nodes).collect();
+        assert_eq!(
+            nodes,
+            vec![9, 72, 504, 3024, 15120, 54720, 148176, 200448, 127872]
+        );
+        let positions: Vec<u64> = counts[..9].iter().map(|ply| ply.positions).collect();
+        assert_eq!(positions, vec![9, 72, 252, 756, 1260, 1520, 1140, 390, 78]);
+
+        // The 255,168 possible games of tic-tac-toe
+        let wins: u64 = counts.iter().map(|ply| ply.wins).sum();
+        let draws: u64 = counts.iter().map(|ply| ply.draws).sum();
+        assert_eq!(counts[4].wins, 1440);
+        assert_eq!(draws, 46080);
+        assert_eq!(wins + draws, 255168);
+    }
+}
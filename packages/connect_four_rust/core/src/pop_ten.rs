@@ -0,0 +1,406 @@
+// core/src/pop_ten.rs
+
+use crate::{Board, Cell, Color, ConnectFour, GameState, HistoryError, Move, MoveError};
+use serde::{Deserialize, Serialize};
+
+/// How many pieces a player must collect to win a Pop 10 game.
+pub const POP_TEN_TARGET: usize = 10;
+
+/// Score of each collected piece in `ConnectFour::pop_ten_score`.
+const COLLECTED_SCORE: i32 = 10_000_000;
+
+/// The phases of a Pop 10 game.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PopTenPhase {
+    /// Players take turns dropping pieces until the board is full.
+    #[default]
+    Setup,
+    /// The player to move pops one of their pieces out of the bottom row.
+    Pop,
+    /// The player to move popped a piece that was not part of a line and must
+    /// drop it back into a column.
+    Return,
+}
+
+/// The Pop 10 part of a `GameState`: the phase and each player's collection.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PopTenState {
+    pub phase: PopTenPhase,
+    pub red_collected: usize,
+    pub yellow_collected: usize,
+    /// Every pop played so far, oldest first. The board does not show whose
+    /// piece was popped or whether it was kept, so `ConnectFour::undo` takes
+    /// pops back from here.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pops: Vec<PopRecord>,
+}
+
+/// A pop of a Pop 10 game: whose piece it was and whether it was collected.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PopRecord {
+    pub player: Color,
+    pub collected: bool,
+}
+
+impl PopTenState {
+    /// How many pieces a player has collected. Pop 10 is a two-player game, so
+    /// other colors never collect any.
+    pub fn collected(&self, player: Color) -> usize {
+        match player {
+            Color::Red => self.red_collected,
+            Color::Yellow => self.yellow_collected,
+            Color::Green | Color::Blue => 0,
+        }
+    }
+
+    fn collect(&mut self, player: Color) {
+        match player {
+            Color::Red => self.red_collected += 1,
+            Color::Yellow => self.yellow_collected += 1,
+            Color::Green | Color::Blue => {}
+        }
+    }
+
+    fn uncollect(&mut self, player: Color) {
+        match player {
+            Color::Red => self.red_collected = self.red_collected.saturating_sub(1),
+            Color::Yellow => self.yellow_collected = self.yellow_collected.saturating_sub(1),
+            Color::Green | Color::Blue => {}
+        }
+    }
+}
+
+impl ConnectFour {
+    /// Plays a move by the rules of `Variant::PopTen`.
+    ///
+    /// In the setup phase players drop pieces in turn until the board is full.
+    /// Then each turn pops one of the mover's pieces out of the bottom row. A
+    /// piece that was part of a line is collected and the mover plays again;
+    /// any other piece must be dropped back in by the mover, which ends their
+    /// turn. A player without a piece in the bottom row passes, and the game is
+    /// drawn when neither player has one.
+    pub(crate) fn try_play_pop_ten(
+        &self,
+        state: &GameState,
+        mv: Move,
+    ) -> Result<GameState, MoveError> {
+        if state.is_game_over {
+            return Err(MoveError::GameOver);
+        }
+        let column = mv.column();
+        if column >= self.num_columns {
+            return Err(MoveError::ColumnOutOfRange {
+                column,
+                num_columns: self.num_columns,
+            });
+        }
+
+        let player = state.current_player;
+        let mut progress = state.pop_ten.clone().unwrap_or_default();
+        let mut board = state.board.clone();
+        let mut next_player = player.opponent();
+        match (progress.phase, mv) {
+            (PopTenPhase::Setup | PopTenPhase::Return, Move::Drop(_)) => {
+                let row = board[column]
+                    .iter()
+                    .rposition(|&cell| cell == Cell::Empty)
+                    .ok_or(MoveError::ColumnFull { column })?;
+                board[column][row] = Cell::Filled(player);
+                if progress.phase == PopTenPhase::Return || self.is_board_full(&board) {
+                    progress.phase = PopTenPhase::Pop;
+                }
+            }
+            (PopTenPhase::Pop, Move::Pop { .. }) => {
+                let bottom = self.num_rows - 1;
+                if board[column][bottom] != Cell::Filled(player) {
+                    return Err(MoveError::CannotPop { column });
+                }
+                let collected = self.completes_line(&board, column, bottom, player);
+                if collected {
+                    progress.collect(player);
+                } else {
+                    progress.phase = PopTenPhase::Return;
+                }
+                progress.pops.push(PopRecord { player, collected });
+                board[column].pop();
+                board[column].insert(0, Cell::Empty);
+                next_player = player;
+            }
+            _ => return Err(MoveError::WrongPhase),
+        }
+
+        let winner = (progress.collected(player) >= POP_TEN_TARGET).then_some(player);
+        let mut is_game_over = winner.is_some();
+        if !is_game_over && progress.phase == PopTenPhase::Pop {
+            // A player with nothing to pop passes
+            if !self.can_pop_bottom(&board, next_player) {
+                next_player = next_player.opponent();
+                is_game_over = !self.can_pop_bottom(&board, next_player);
+            }
+        }
+
+        let mut moves = state.moves.clone();
+        moves.push(mv);
+        Ok(GameState {
+            board,
+            current_player: next_player,
+            winner,
+            is_game_over,
+            moves,
+            undone_moves: Vec::new(),
+            pop_ten: Some(progress),
+        })
+    }
+
+    /// The moves of the player to move in a Pop 10 game.
+    pub(crate) fn pop_ten_moves(&self, state: &GameState) -> Vec<Move> {
+        let player = state.current_player;
+        match state.pop_ten.as_ref().map(|progress| progress.phase) {
+            None | Some(PopTenPhase::Setup | PopTenPhase::Return) => self
+                .get_valid_columns(&state.board)
+                .into_iter()
+                .map(Move::Drop)
+                .collect(),
+            Some(PopTenPhase::Pop) => (0..self.num_columns)
+                .filter(|&column| state.board[column][self.num_rows - 1] == Cell::Filled(player))
+                .map(|column| Move::Pop { column })
+                .collect(),
+        }
+    }
+
+    /// Takes back the last Pop 10 move. A drop is taken back by its mover,
+    /// the owner of the top piece of the column, and a pop from its record in
+    /// `PopTenState::pops`.
+    pub(crate) fn undo_pop_ten(&self, state: &GameState) -> Result<GameState, HistoryError> {
+        let (&mv, earlier) = state
+            .moves
+            .split_last()
+            .ok_or(HistoryError::NothingToUndo)?;
+        let mut progress = state.pop_ten.clone().unwrap_or_default();
+        let player = match mv {
+            Move::Pop { .. } => {
+                let record = progress.pops.pop().ok_or(HistoryError::Inconsistent)?;
+                if record.collected {
+                    progress.uncollect(record.player);
+                }
+                progress.phase = PopTenPhase::Pop;
+                record.player
+            }
+            _ => {
+                // Only a piece that was not collected is dropped right after a pop
+                progress.phase = match earlier.last() {
+                    Some(Move::Pop { .. }) => PopTenPhase::Return,
+                    _ => PopTenPhase::Setup,
+                };
+                match state
+                    .board
+                    .get(mv.column())
+                    .and_then(|column| column.iter().find(|&&cell| cell != Cell::Empty))
+                {
+                    Some(&Cell::Filled(player)) => player,
+                    _ => return Err(HistoryError::Inconsistent),
+                }
+            }
+        };
+
+        let mut board = state.board.clone();
+        self.take_back(&mut board, mv, player)?;
+        let mut undone_moves = state.undone_moves.clone();
+        undone_moves.push(mv);
+        Ok(GameState {
+            board,
+            current_player: player,
+            winner: None,
+            is_game_over: false,
+            moves: earlier.to_vec(),
+            undone_moves,
+            pop_ten: Some(progress),
+        })
+    }
+
+    /// Scores a Pop 10 position for `player`: collected pieces count most, then
+    /// the evaluator's opinion of the board.
+    pub(crate) fn pop_ten_score(&self, state: &GameState, player: Color) -> i32 {
+        let collected = state.pop_ten.as_ref().map_or(0, |progress| {
+            progress.collected(player) as i32 - progress.collected(player.opponent()) as i32
+        });
+        collected * COLLECTED_SCORE + self.evaluator.evaluate(self, &state.board, player)
+    }
+
+    fn can_pop_bottom(&self, board: &Board, player: Color) -> bool {
+        board
+            .iter()
+            .any(|column| column[self.num_rows - 1] == Cell::Filled(player))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectFourConfig, Quality, Variant};
+
+    #[test]
+    fn test_setup_then_pop_phase() {
+        let game = ConnectFourConfig::new()
+            .num_columns(4)
+            .num_rows(2)
+            .variant(Variant::PopTen)
+            .max_depth(2)
+            .seed(3)
+            .build()
+            .unwrap();
+        let state = game.create_initial_state();
+        assert_eq!(state.pop_ten, Some(PopTenState::default()));
+        assert_eq!(
+            game.try_play(&state, Move::Pop { column: 0 }),
+            Err(MoveError::WrongPhase)
+        );
+
+        // Red fills the bottom row, so every red piece there is part of a line
+        let state = game.state_from_moves("11223344").unwrap();
+        assert_eq!(state.pop_ten.as_ref().unwrap().phase, PopTenPhase::Pop);
+        // Plain drops follow the setup rules too, so the line does not end the game
+        let mut dropped = game.create_initial_state();
+        for col in [0, 0, 1, 1, 2, 2, 3, 3] {
+            dropped = game.place_piece(&dropped, col);
+        }
+        assert_eq!(dropped, state);
+        assert!(!state.is_game_over);
+        assert_eq!(state.current_player, Color::Red);
+        assert_eq!(game.legal_moves(&state).len(), 4);
+        assert_eq!(
+            game.try_play(&state, Move::Drop(0)),
+            Err(MoveError::WrongPhase)
+        );
+    }
+
+    #[test]
+    fn test_collect_and_return() {
+        let game = ConnectFourConfig::new()
+            .num_columns(4)
+            .num_rows(2)
+            .variant(Variant::PopTen)
+            .max_depth(2)
+            .seed(3)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("11223344").unwrap();
+
+        // A piece from a line is kept and the player goes again
+        let state = game.try_play(&state, Move::Pop { column: 0 }).unwrap();
+        assert_eq!(state.pop_ten.as_ref().unwrap().red_collected, 1);
+        assert_eq!(state.current_player, Color::Red);
+
+        // The line is broken, so the next piece has to go back in
+        let state = game.try_play(&state, Move::Pop { column: 1 }).unwrap();
+        assert_eq!(state.pop_ten.as_ref().unwrap().phase, PopTenPhase::Return);
+        assert_eq!(state.pop_ten.as_ref().unwrap().red_collected, 1);
+        assert_eq!(game.legal_moves(&state), vec![Move::Drop(0), Move::Drop(1)]);
+
+        let returned = game.try_play(&state, Move::Drop(0)).unwrap();
+        assert_eq!(returned.pop_ten.as_ref().unwrap().phase, PopTenPhase::Pop);
+        assert_eq!(returned.current_player, Color::Yellow);
+        assert_eq!(returned.to_move_string(), "11223344^1^21");
+
+        let undone = game.undo(&returned).unwrap();
+        assert_eq!(undone.board, state.board);
+        assert_eq!(undone.pop_ten, state.pop_ten);
+        assert_eq!(game.redo(&undone).unwrap(), returned);
+    }
+
+    #[test]
+    fn test_computer_pops_in_pop_phase() {
+        let game = ConnectFourConfig::new()
+            .num_columns(4)
+            .num_rows(2)
+            .variant(Variant::PopTen)
+            .max_depth(2)
+            .seed(3)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("11223344").unwrap();
+        for quality in [Quality::Bad, Quality::Medium, Quality::Best, Quality::Mcts] {
+            let mv = game.get_computer_move(&state, quality);
+            assert!(matches!(mv, Move::Pop { .. }), "{:?} is not a pop", mv);
+            assert!(game.try_play(&state, mv).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_undo_from_fen() {
+        let game = ConnectFourConfig::new()
+            .num_columns(4)
+            .num_rows(2)
+            .variant(Variant::PopTen)
+            .build()
+            .unwrap();
+        // A loaded game has no history before the board
+        let loaded = game.state_from_fen("yyyy/rrrr r").unwrap();
+        let mut states = vec![loaded.clone()];
+        for mv in [
+            Move::Pop { column: 0 },
+            Move::Pop { column: 1 },
+            Move::Drop(0),
+        ] {
+            states.push(game.try_play(states.last().unwrap(), mv).unwrap());
+        }
+        assert_eq!(
+            states[1].pop_ten.as_ref().unwrap().pops,
+            vec![PopRecord {
+                player: Color::Red,
+                collected: true
+            }]
+        );
+
+        let mut state = states.pop().unwrap();
+        while let Some(expected) = states.pop() {
+            state = game.undo(&state).unwrap();
+            assert_eq!(state.board, expected.board);
+            assert_eq!(state.current_player, expected.current_player);
+            assert_eq!(state.pop_ten, expected.pop_ten);
+        }
+        assert_eq!(game.undo(&state), Err(HistoryError::NothingToUndo));
+    }
+
+    #[test]
+    fn test_tenth_piece_wins() {
+        let game = ConnectFourConfig::new()
+            .num_columns(6)
+            .num_rows(2)
+            .variant(Variant::PopTen)
+            .max_depth(2)
+            .seed(3)
+            .build()
+            .unwrap();
+        // Red's bottom row is rrrryr: only the first four columns are in a line
+        let mut state = game.state_from_moves("112233446556").unwrap();
+        state.pop_ten = Some(PopTenState {
+            red_collected: POP_TEN_TARGET - 1,
+            ..state.pop_ten.unwrap()
+        });
+        for quality in [Quality::Medium, Quality::Best] {
+            let mv = game.get_variant_move(&state, quality);
+            assert!(mv.column() < 4, "{:?} should collect", mv);
+            let won = game.try_play(&state, mv).unwrap();
+            assert_eq!(won.winner, Some(Color::Red));
+            assert!(won.is_game_over);
+        }
+    }
+
+    #[test]
+    fn test_tactical_move_keeps_extra_turn() {
+        let game = ConnectFourConfig::new()
+            .num_columns(5)
+            .num_rows(2)
+            .variant(Variant::PopTen)
+            .seed(3)
+            .build()
+            .unwrap();
+        // Popping an end column keeps a line of four for the winning pop
+        let state = game.state_from_fen("yyyyy/rrrrr r pop:8:0").unwrap();
+        let columns: Vec<_> = (0..20)
+            .map(|_| game.get_variant_move(&state, Quality::Medium).column())
+            .collect();
+        assert!(columns.iter().any(|&column| column == 0 || column == 4));
+    }
+}
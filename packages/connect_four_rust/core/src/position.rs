@@ -0,0 +1,691 @@
+// core/src/position.rs
+
+use crate::{Board, Cell, Color, ConnectFour, Coordinate, GameState, Topology, Variant};
+
+/// A compact bitboard encoding of a game position.
+///
+/// Every column takes `height + 1` bits, starting with the bottom cell. The extra
+/// bit on top of each column is never set, so shifted lines cannot wrap from one
+/// column into the next. `current` holds the stones of the player to move and
+/// `mask` holds every stone on the board. `hash` is the Zobrist hash of the
+/// stones and the side to move, kept up to date as stones are played, and
+/// `mirror_hash` is the hash of the position reflected left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    current: u64,
+    mask: u64,
+    hash: u64,
+    mirror_hash: u64,
+    bottom: u64,
+    width: u8,
+    height: u8,
+    winning_length: u8,
+    moves: u8,
+    current_player: Color,
+    topology: Topology,
+}
+
+impl Position {
+    /// Returns true if a board of the given size fits in a 64-bit bitboard.
+    pub fn fits(num_columns: usize, num_rows: usize) -> bool {
+        num_columns > 0 && num_rows > 0 && num_rows + 2 < 64 && num_columns * (num_rows + 1) <= 64
+    }
+
+    /// Creates an empty position, or `None` if the board does not fit in 64 bits.
+    pub fn new(num_columns: usize, num_rows: usize, winning_length: usize) -> Option<Self> {
+        if !Self::fits(num_columns, num_rows) || winning_length == 0 || winning_length > 64 {
+            return None;
+        }
+        let bottom = (0..num_columns).fold(0, |bottom, col| bottom | 1 << (col * (num_rows + 1)));
+        Some(Position {
+            current: 0,
+            mask: 0,
+            hash: 0,
+            mirror_hash: 0,
+            bottom,
+            width: num_columns as u8,
+            height: num_rows as u8,
+            winning_length: winning_length as u8,
+            moves: 0,
+            current_player: Color::Red,
+            topology: Topology::Flat,
+        })
+    }
+
+    /// Sets how the edges of the board connect.
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Builds a position from a game state.
+    ///
+    /// Returns `None` if the board does not match the game's dimensions, does not fit
+    /// in 64 bits, or has a stone floating above an empty cell, for variants
+    /// other than `Variant::Standard`, whose rules a `Position` does not follow, and
+    /// for games of more than two players.
+    pub fn from_state(game: &ConnectFour, state: &GameState) -> Option<Self> {
+        if game.variant != Variant::Standard || game.num_players > 2 {
+            return None;
+        }
+        let mut position = Self::new(game.num_columns, game.num_rows, game.winning_length)?
+            .with_topology(game.topology);
+        if state.board.len() != game.num_columns {
+            return None;
+        }
+
+        let mut red = 0;
+        for (col, column) in state.board.iter().enumerate() {
+            if column.len() != game.num_rows {
+                return None;
+            }
+            let mut seen_empty = false;
+            for (height, cell) in column.iter().rev().enumerate() {
+                match cell {
+                    Cell::Empty => seen_empty = true,
+                    Cell::Filled(_) if seen_empty => return None,
+                    Cell::Filled(color) => {
+                        let bit = position.bit(col, height);
+                        position.mask |= bit;
+                        if *color == Color::Red {
+                            red |= bit;
+                        }
+                        position.hash ^= zobrist_key(*color, bit);
+                        position.mirror_hash ^= zobrist_key(*color, position.mirror_stone(bit));
+                        position.moves += 1;
+                    }
+                }
+            }
+        }
+
+        position.current_player = state.current_player;
+        if state.current_player == Color::Yellow {
+            position.hash ^= ZOBRIST_YELLOW_TO_MOVE;
+            position.mirror_hash ^= ZOBRIST_YELLOW_TO_MOVE;
+        }
+        position.current = if state.current_player == Color::Red {
+            red
+        } else {
+            position.mask ^ red
+        };
+        Some(position)
+    }
+
+    /// Rebuilds a two-player position on a flat board from its `key`, or returns
+    /// `None` if the key does not describe one. The side to move follows from
+    /// the number of stones.
+    pub(crate) fn from_key(
+        num_columns: usize,
+        num_rows: usize,
+        winning_length: usize,
+        key: u64,
+    ) -> Option<Self> {
+        let mut position = Self::new(num_columns, num_rows, winning_length)?;
+        let column_bits = num_rows + 1;
+        if num_columns * column_bits < 64 && key >> (num_columns * column_bits) != 0 {
+            return None;
+        }
+        // Each column of the key is its stones of the player to move added to
+        // a run of ones as tall as the column, one less than a power of two
+        for col in 0..num_columns {
+            let shift = col * column_bits;
+            let sum = ((key >> shift) & ((1 << column_bits) - 1)) + 1;
+            let stones = sum.ilog2();
+            if stones as usize > num_rows {
+                return None;
+            }
+            position.mask |= ((1 << stones) - 1) << shift;
+            position.current |= (sum - (1 << stones)) << shift;
+        }
+
+        position.moves = position.mask.count_ones() as u8;
+        position.current_player = Color::ALL[position.moves as usize % 2];
+        let mut stones = position.mask;
+        while stones != 0 {
+            let bit = stones & stones.wrapping_neg();
+            stones ^= bit;
+            let color = if position.current & bit != 0 {
+                position.current_player
+            } else {
+                position.current_player.opponent()
+            };
+            position.hash ^= zobrist_key(color, bit);
+            position.mirror_hash ^= zobrist_key(color, position.mirror_stone(bit));
+        }
+        if position.current_player == Color::Yellow {
+            position.hash ^= ZOBRIST_YELLOW_TO_MOVE;
+            position.mirror_hash ^= ZOBRIST_YELLOW_TO_MOVE;
+        }
+        Some(position)
+    }
+
+    /// Converts the position back into a game state. The move history is empty,
+    /// since a position does not record the order its stones were played in.
+    pub fn to_state(&self) -> GameState {
+        let mut board = vec![vec![Cell::Empty; self.height as usize]; self.width as usize];
+        let red = self.stones(Color::Red);
+        for (col, column) in board.iter_mut().enumerate() {
+            for (height, cell) in column.iter_mut().rev().enumerate() {
+                let bit = self.bit(col, height);
+                if self.mask & bit != 0 {
+                    *cell = Cell::Filled(if red & bit != 0 {
+                        Color::Red
+                    } else {
+                        Color::Yellow
+                    });
+                }
+            }
+        }
+
+        let winner = self.winner();
+        GameState {
+            board,
+            current_player: self.current_player,
+            winner,
+            is_game_over: winner.is_some() || self.is_full(),
+            moves: Vec::new(),
+            undone_moves: Vec::new(),
+            pop_ten: None,
+        }
+    }
+
+    /// Number of columns on the board.
+    pub fn width(&self) -> usize {
+        self.width as usize
+    }
+
+    /// Number of rows on the board.
+    pub fn height(&self) -> usize {
+        self.height as usize
+    }
+
+    /// Number of stones needed in a line to win.
+    pub fn winning_length(&self) -> usize {
+        self.winning_length as usize
+    }
+
+    /// Number of stones played so far.
+    pub fn moves(&self) -> usize {
+        self.moves as usize
+    }
+
+    /// The player whose turn it is.
+    pub fn current_player(&self) -> Color {
+        self.current_player
+    }
+
+    /// Bitmask of the stones of the given player.
+    pub fn stones(&self, player: Color) -> u64 {
+        if player == self.current_player {
+            self.current
+        } else {
+            self.current ^ self.mask
+        }
+    }
+
+    /// Bitmask of every stone on the board.
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    /// A key that uniquely identifies the position for its board size.
+    pub fn key(&self) -> u64 {
+        self.current.wrapping_add(self.mask)
+    }
+
+    /// The Zobrist hash of the position, including the side to move.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// The position reflected left to right. Lines are unchanged by the
+    /// reflection, on flat boards and cylinders alike, so the mirror image has
+    /// the same value and its moves are the original ones under `mirror_column`.
+    pub fn mirrored(&self) -> Position {
+        Position {
+            current: self.mirror(self.current),
+            mask: self.mirror(self.mask),
+            hash: self.mirror_hash,
+            mirror_hash: self.hash,
+            ..*self
+        }
+    }
+
+    /// The column a column is reflected to by `mirrored`. Applying it twice
+    /// gives back the original column.
+    pub fn mirror_column(&self, col: Coordinate) -> Coordinate {
+        self.width() - 1 - col
+    }
+
+    /// The canonical form of the position: itself or its mirror image, whichever
+    /// has the smaller key, and whether it is the mirror image. Moves found for
+    /// a mirrored canonical form map back with `mirror_column`.
+    pub fn canonical(&self) -> (Position, bool) {
+        let mirrored = self.mirrored();
+        if mirrored.key() < self.key() {
+            (mirrored, true)
+        } else {
+            (*self, false)
+        }
+    }
+
+    /// Like `key`, but the same for a position and its mirror image.
+    pub fn canonical_key(&self) -> u64 {
+        self.canonical().0.key()
+    }
+
+    /// Like `hash`, but the same for a position and its mirror image, along with
+    /// whether the mirror image's hash was taken. Cheaper than `canonical`.
+    pub(crate) fn canonical_hash(&self) -> (u64, bool) {
+        if self.mirror_hash < self.hash {
+            (self.mirror_hash, true)
+        } else {
+            (self.hash, false)
+        }
+    }
+
+    /// Returns true if a stone can be dropped into the column.
+    pub fn can_play(&self, col: usize) -> bool {
+        col < self.width() && self.mask & self.top_mask(col) == 0
+    }
+
+    /// Drops a stone for the current player. The column must be playable.
+    pub fn play(&mut self, col: usize) {
+        self.play_stone((self.mask + self.bottom_mask(col)) & self.column_mask(col));
+    }
+
+    /// Plays a single-bit move taken from `possible` or `non_losing_moves`.
+    pub(crate) fn play_stone(&mut self, stone: u64) {
+        self.hash ^= zobrist_key(self.current_player, stone) ^ ZOBRIST_YELLOW_TO_MOVE;
+        self.mirror_hash ^=
+            zobrist_key(self.current_player, self.mirror_stone(stone)) ^ ZOBRIST_YELLOW_TO_MOVE;
+        self.current ^= self.mask;
+        self.mask |= stone;
+        self.moves += 1;
+        self.current_player = self.current_player.opponent();
+    }
+
+    /// Returns true if dropping a stone into the column wins for the current player.
+    pub fn is_winning_move(&self, col: usize) -> bool {
+        let stones = self.current | ((self.mask + self.bottom_mask(col)) & self.column_mask(col));
+        self.has_alignment(stones)
+    }
+
+    /// Returns true if the current player can win with their next stone.
+    pub fn can_win_next(&self) -> bool {
+        self.winning_cells(self.current) & self.possible() != 0
+    }
+
+    /// Bitmask of the cells where a stone can be dropped right now.
+    pub(crate) fn possible(&self) -> u64 {
+        (self.mask + self.bottom) & self.board_mask()
+    }
+
+    /// Playable moves that do not hand the opponent an immediate win.
+    ///
+    /// Returns 0 if every move loses: the opponent has two immediate wins, or the
+    /// only block would let them win on top of it.
+    pub(crate) fn non_losing_moves(&self) -> u64 {
+        let mut possible = self.possible();
+        let opponent_wins = self.winning_cells(self.current ^ self.mask);
+        let forced = possible & opponent_wins;
+        if forced != 0 {
+            if forced & (forced - 1) != 0 {
+                return 0;
+            }
+            possible = forced;
+        }
+        possible & !(opponent_wins >> 1)
+    }
+
+    /// Number of open lines the current player would threaten after the move.
+    pub(crate) fn move_score(&self, stone: u64) -> u32 {
+        self.winning_cells(self.current | stone).count_ones()
+    }
+
+    /// Empty cells that would complete a line of `winning_length` for the stones.
+    pub(crate) fn winning_cells(&self, stones: u64) -> u64 {
+        // Lines across the seam of a cylinder are found by turning the board so
+        // that they no longer cross it
+        let cells = self.rotations().fold(0, |cells, turn| {
+            let turned = flat_winning_cells(
+                self.rotate(stones, turn),
+                self.height(),
+                self.winning_length(),
+            );
+            cells | self.rotate(turned, (self.width() - turn) % self.width())
+        });
+        cells & (self.board_mask() ^ self.mask)
+    }
+
+    /// Bitmask of every playable cell on the board.
+    pub(crate) fn board_mask(&self) -> u64 {
+        self.bottom * ((1 << self.height) - 1)
+    }
+
+    /// Returns true if every cell on the board is filled.
+    pub fn is_full(&self) -> bool {
+        self.moves() == self.width() * self.height()
+    }
+
+    /// Returns the player with a complete line, if any.
+    pub fn winner(&self) -> Option<Color> {
+        let last_player = self.current_player.opponent();
+        if self.has_alignment(self.stones(last_player)) {
+            Some(last_player)
+        } else if self.has_alignment(self.current) {
+            Some(self.current_player)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the game is won or the board is full.
+    pub fn is_game_over(&self) -> bool {
+        self.is_full() || self.winner().is_some()
+    }
+
+    /// Returns true if the stones contain a line of `winning_length`.
+    pub fn has_alignment(&self, stones: u64) -> bool {
+        self.rotations().any(|turn| {
+            has_alignment(
+                self.rotate(stones, turn),
+                self.height(),
+                self.winning_length(),
+            )
+        })
+    }
+
+    /// How many columns the board is turned by to check lines: only 0 on a flat
+    /// board, and enough turns to straighten any line across a cylinder's seam.
+    fn rotations(&self) -> std::ops::Range<usize> {
+        match self.topology {
+            Topology::Flat => 0..1,
+            Topology::Cylinder => 0..self.winning_length().min(self.width()),
+        }
+    }
+
+    /// Moves every column `turn` columns to the right, wrapping around.
+    fn rotate(&self, bits: u64, turn: usize) -> u64 {
+        if turn == 0 {
+            return bits;
+        }
+        let stride = self.height() + 1;
+        let columns = shl(1, self.width() * stride).wrapping_sub(1);
+        (shl(bits, turn * stride) | shr(bits, (self.width() - turn) * stride)) & columns
+    }
+
+    /// Reflects bits left to right, column by column.
+    fn mirror(&self, bits: u64) -> u64 {
+        let stride = self.height() + 1;
+        let column = (1 << stride) - 1;
+        (0..self.width()).fold(0, |mirrored, col| {
+            let cells = (bits >> (col * stride)) & column;
+            mirrored | cells << (self.mirror_column(col) * stride)
+        })
+    }
+
+    /// Reflects a single stone; cheaper than `mirror` on the hot path.
+    fn mirror_stone(&self, stone: u64) -> u64 {
+        let stride = self.height() + 1;
+        let bit = stone.trailing_zeros() as usize;
+        1 << (self.mirror_column(bit / stride) * stride + bit % stride)
+    }
+
+    /// Bitmask of a line of cells given as `(column, row)` pairs with row 0 at the top.
+    pub(crate) fn cells_mask(&self, cells: &[(Coordinate, usize)]) -> u64 {
+        cells.iter().fold(0, |mask, &(col, row)| {
+            mask | self.bit(col, self.height() - 1 - row)
+        })
+    }
+
+    /// Bitmask of the playable cells in a column.
+    pub(crate) fn column_mask(&self, col: usize) -> u64 {
+        ((1 << self.height) - 1) << (col * (self.height() + 1))
+    }
+
+    fn bottom_mask(&self, col: usize) -> u64 {
+        1 << (col * (self.height() + 1))
+    }
+
+    fn top_mask(&self, col: usize) -> u64 {
+        1 << (self.height() - 1 + col * (self.height() + 1))
+    }
+
+    /// The bit for a cell, counting `height` up from the bottom of the column.
+    fn bit(&self, col: usize, height: usize) -> u64 {
+        1 << (col * (self.height() + 1) + height)
+    }
+}
+
+/// Empty or filled cells that would complete a line of `length` for the stones on
+/// a flat board with `num_rows`.
+fn flat_winning_cells(stones: u64, num_rows: usize, length: usize) -> u64 {
+    let mut cells = 0;
+    for shift in [1, num_rows, num_rows + 1, num_rows + 2] {
+        // A cell completes a line with `before` stones on one side and the rest
+        // on the other.
+        for before in 0..length {
+            let mut line = !0;
+            for i in 1..=before {
+                line &= shr(stones, i * shift);
+            }
+            for i in 1..length - before {
+                line &= shl(stones, i * shift);
+            }
+            cells |= line;
+        }
+    }
+    cells
+}
+
+/// Returns true if the stones contain a line of `length` on a board with `num_rows`.
+pub(crate) fn has_alignment(stones: u64, num_rows: usize, length: usize) -> bool {
+    let num_rows = num_rows as u32;
+    [1, num_rows, num_rows + 1, num_rows + 2]
+        .iter()
+        .any(|&shift| (1..length).fold(stones, |run, _| run & (run >> shift)) != 0)
+}
+
+/// Packs one player's stones from a board into a bitmask in `Position` layout.
+pub(crate) fn board_stones(board: &Board, num_rows: usize, player: Color) -> u64 {
+    let mut stones = 0;
+    for (col, column) in board.iter().enumerate() {
+        for (height, cell) in column.iter().rev().enumerate() {
+            if *cell == Cell::Filled(player) {
+                stones |= 1 << (col * (num_rows + 1) + height);
+            }
+        }
+    }
+    stones
+}
+
+/// Zobrist keys for a stone of each color on each of the 64 bits.
+const ZOBRIST_STONES: [[u64; 64]; 2] = zobrist_table();
+
+/// Zobrist key toggled whenever it is Yellow's turn.
+const ZOBRIST_YELLOW_TO_MOVE: u64 = splitmix64(0x7965_6c6c_6f77).1;
+
+fn zobrist_key(player: Color, stone: u64) -> u64 {
+    ZOBRIST_STONES[player.index()][stone.trailing_zeros() as usize]
+}
+
+/// Fills the Zobrist table from a fixed seed so hashes are stable across builds.
+const fn zobrist_table() -> [[u64; 64]; 2] {
+    let mut table = [[0; 64]; 2];
+    let mut state = 0x636f_6e6e_6563_7434;
+    let mut player = 0;
+    while player < 2 {
+        let mut bit = 0;
+        while bit < 64 {
+            let (next_state, key) = splitmix64(state);
+            table[player][bit] = key;
+            state = next_state;
+            bit += 1;
+        }
+        player += 1;
+    }
+    table
+}
+
+/// One step of the SplitMix64 generator, returning the new state and its output.
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+fn shl(bits: u64, shift: usize) -> u64 {
+    bits.checked_shl(shift as u32).unwrap_or(0)
+}
+
+fn shr(bits: u64, shift: usize) -> u64 {
+    bits.checked_shr(shift as u32).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `to_state` cannot recover the order the stones were played in.
+    fn without_history(state: &GameState) -> GameState {
+        GameState {
+            moves: Vec::new(),
+            undone_moves: Vec::new(),
+            pop_ten: None,
+            ..state.clone()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_state() {
+        let game = ConnectFour::new();
+        let mut state = game.create_initial_state();
+        for &col in &[3, 3, 2, 4, 0] {
+            state = game.place_piece(&state, col);
+        }
+        let position = Position::from_state(&game, &state).unwrap();
+        assert_eq!(position.moves(), 5);
+        assert_eq!(position.current_player(), Color::Yellow);
+        assert_eq!(position.to_state(), without_history(&state));
+    }
+
+    #[test]
+    fn test_mirror_and_canonical_form() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("4453").unwrap();
+        let mirror = game.state_from_moves("4435").unwrap();
+        let position = Position::from_state(&game, &state).unwrap();
+        let mirrored = Position::from_state(&game, &mirror).unwrap();
+
+        assert_eq!(position.mirrored(), mirrored);
+        assert_eq!(position.mirrored().mirrored(), position);
+        assert_eq!(position.mirror_column(0), 6);
+        assert_eq!(position.canonical_key(), mirrored.canonical_key());
+        assert_eq!(position.canonical_hash().0, mirrored.canonical_hash().0);
+        let (canonical, flipped) = position.canonical();
+        assert_eq!(canonical, mirrored.canonical().0);
+        assert_ne!(flipped, mirrored.canonical().1);
+
+        // The mirror hash stays in step as stones are played
+        let mut played = position;
+        played.play(0);
+        let mut mirrored_played = mirrored;
+        mirrored_played.play(6);
+        assert_eq!(played.mirrored(), mirrored_played);
+
+        assert_eq!(Position::from_key(7, 6, 4, position.key()), Some(position));
+
+        // A symmetric position is its own mirror image
+        let center = Position::from_state(&game, &game.state_from_moves("44").unwrap()).unwrap();
+        assert_eq!(center.canonical(), (center, false));
+    }
+
+    #[test]
+    fn test_play_matches_place_piece() {
+        let game = ConnectFour::new();
+        let mut state = game.create_initial_state();
+        let mut position = Position::from_state(&game, &state).unwrap();
+        for &col in &[3, 4, 3, 4, 3, 4] {
+            assert!(!position.is_winning_move(col));
+            state = game.place_piece(&state, col);
+            position.play(col);
+            assert_eq!(position.to_state(), without_history(&state));
+        }
+        assert!(position.is_winning_move(3));
+        assert!(!position.is_winning_move(2));
+    }
+
+    #[test]
+    fn test_hash_matches_from_state() {
+        let game = ConnectFour::new();
+        let mut state = game.create_initial_state();
+        let mut position = Position::from_state(&game, &state).unwrap();
+        for &col in &[3, 2, 3, 4, 6] {
+            state = game.place_piece(&state, col);
+            position.play(col);
+            assert_eq!(
+                position.hash(),
+                Position::from_state(&game, &state).unwrap().hash()
+            );
+        }
+
+        let mut transposed = Position::new(7, 6, 4).unwrap();
+        for &col in &[3, 4, 3, 2, 6] {
+            transposed.play(col);
+        }
+        assert_eq!(position.hash(), transposed.hash());
+        transposed.play(0);
+        assert_ne!(position.hash(), transposed.hash());
+    }
+
+    #[test]
+    fn test_non_losing_moves() {
+        let mut position = Position::new(7, 6, 4).unwrap();
+        for &col in &[0, 6, 1, 6, 2] {
+            position.play(col);
+        }
+        // Yellow has to block the open three at column 3
+        assert!(position.can_play(3));
+        assert_eq!(position.non_losing_moves(), position.bit(3, 0));
+
+        for &col in &[3, 5, 6] {
+            position.play(col);
+        }
+        // Red now has to block Yellow's three in column 6
+        assert!(!position.can_win_next());
+        assert_eq!(position.non_losing_moves(), position.bit(6, 3));
+        position.play(4);
+        assert!(position.can_win_next());
+    }
+
+    #[test]
+    fn test_full_column() {
+        let mut position = Position::new(7, 6, 4).unwrap();
+        for _ in 0..6 {
+            assert!(position.can_play(0));
+            position.play(0);
+        }
+        assert!(!position.can_play(0));
+        assert!(!position.can_play(7));
+    }
+
+    #[test]
+    fn test_rejects_floating_stone() {
+        let game = ConnectFour::new();
+        let mut state = game.create_initial_state();
+        state.board[0][0] = Cell::Filled(Color::Red);
+        assert!(Position::from_state(&game, &state).is_none());
+    }
+
+    #[test]
+    fn test_diagonal_alignment() {
+        let mut position = Position::new(7, 6, 4).unwrap();
+        for &col in &[0, 1, 1, 2, 2, 3, 2, 3, 3, 6] {
+            position.play(col);
+        }
+        assert!(position.is_winning_move(3));
+    }
+}
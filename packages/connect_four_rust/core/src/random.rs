@@ -0,0 +1,29 @@
+// core/src/random.rs
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The random number generator behind the AI's random choices.
+///
+/// ChaCha8 produces the same stream on every platform, so a seeded game plays
+/// out the same on native targets and on wasm32.
+pub(crate) type GameRng = ChaCha8Rng;
+
+pub(crate) fn seeded(seed: u64) -> GameRng {
+    GameRng::seed_from_u64(seed)
+}
+
+/// A generator seeded from `rng`, for a search that needs its own stream.
+pub(crate) fn fork(rng: &mut GameRng) -> GameRng {
+    seeded(rng.gen())
+}
+
+pub(crate) fn from_entropy() -> GameRng {
+    GameRng::from_entropy()
+}
+
+/// Picks an index below `len`. Samples a `u32`, since sampling a `usize` gives
+/// different results on 32-bit and 64-bit targets.
+pub(crate) fn index(rng: &mut GameRng, len: usize) -> usize {
+    rng.gen_range(0..len as u32) as usize
+}
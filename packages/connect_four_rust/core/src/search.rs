@@ -0,0 +1,279 @@
+// core/src/search.rs
+
+use crate::limits::{Budget, Clock, SearchLimits};
+use crate::position::Position;
+use crate::tt::{Bound, TranspositionTable};
+use crate::{Color, ConnectFour, Coordinate};
+
+/// Salt mixed into table keys when Yellow is the root player, since scores are
+/// stored from the root player's point of view.
+const YELLOW_PERSPECTIVE: u64 = 0x5be0_cd19_137e_2179;
+
+/// Depth-limited alpha-beta search over bitboard positions.
+///
+/// Scores are always from the point of view of the player to move at the root and
+/// come from the game's `Evaluator`.
+pub(crate) struct Search<'a> {
+    game: &'a ConnectFour,
+    table: &'a mut TranspositionTable,
+    player: Color,
+    perspective: u64,
+    column_order: Vec<Coordinate>,
+    /// Whether mirror images share table entries; see `Evaluator::is_symmetric`.
+    symmetric: bool,
+    budget: Budget<'a>,
+}
+
+impl<'a> Search<'a> {
+    pub(crate) fn new(
+        game: &'a ConnectFour,
+        table: &'a mut TranspositionTable,
+        root: &Position,
+    ) -> Self {
+        // Move ordering: prioritize center column and adjacent columns
+        let center = root.width() / 2;
+        let mut column_order: Vec<Coordinate> = (0..root.width()).collect();
+        column_order.sort_by_key(|&col| (center as isize - col as isize).abs());
+
+        Search {
+            game,
+            table,
+            player: root.current_player(),
+            perspective: if root.current_player() == Color::Red {
+                0
+            } else {
+                YELLOW_PERSPECTIVE
+            },
+            column_order,
+            symmetric: game.evaluator.is_symmetric(game),
+            budget: Budget::unlimited(),
+        }
+    }
+
+    /// Applies node and time limits. The time limit is measured with `clock`.
+    pub(crate) fn with_limits(mut self, limits: &SearchLimits, clock: &'a dyn Clock) -> Self {
+        self.budget = Budget::new(limits, clock);
+        self
+    }
+
+    /// Returns the column with the highest minimax score at `ConnectFour::max_depth`.
+    pub(crate) fn best_move(&mut self, root: &Position) -> Coordinate {
+        self.search_root(root, self.game.max_depth)
+            .expect("No valid columns available")
+            .0
+    }
+
+    /// Searches one depth at a time and returns the best move from the deepest
+    /// iteration that finished within the limits.
+    ///
+    /// The first iteration always runs to completion so there is always a move.
+    pub(crate) fn iterative_deepening(&mut self, root: &Position, max_depth: usize) -> Coordinate {
+        let empty_cells = root.width() * root.height() - root.moves();
+        let max_depth = max_depth.min(empty_cells.saturating_sub(1));
+
+        let mut best_column = None;
+        for depth in 0..=max_depth {
+            match self.search_root(root, depth) {
+                Some((col, _)) => best_column = Some(col),
+                None => break,
+            }
+            self.budget.allow_abort();
+        }
+
+        best_column.expect("No valid columns available")
+    }
+
+    /// Scores every root move with iterative deepening, returning each playable
+    /// column with its score and principal variation from the deepest iteration
+    /// that finished within the limits.
+    pub(crate) fn analyze(
+        &mut self,
+        root: &Position,
+        max_depth: usize,
+    ) -> Vec<(Coordinate, i32, Vec<Coordinate>)> {
+        let empty_cells = root.width() * root.height() - root.moves();
+        let max_depth = max_depth.min(empty_cells.saturating_sub(1));
+
+        let mut analysis = Vec::new();
+        for depth in 0..=max_depth {
+            let mut scores = Vec::new();
+            for col in (0..root.width()).filter(|&col| root.can_play(col)) {
+                let mut child = *root;
+                child.play(col);
+                // A full window gives every move its exact score, not just a bound
+                let score = self.minimax(&child, depth, i32::MIN, i32::MAX, false);
+                if self.budget.aborted() {
+                    return analysis;
+                }
+                let mut variation = vec![col];
+                variation.extend(self.principal_variation(&child, depth));
+                scores.push((col, score, variation));
+            }
+            analysis = scores;
+            self.budget.allow_abort();
+        }
+        analysis
+    }
+
+    /// Follows the table's best moves from `position` for up to `depth` plies.
+    fn principal_variation(&self, position: &Position, depth: usize) -> Vec<Coordinate> {
+        let mut position = *position;
+        let mut variation = Vec::new();
+        while variation.len() < depth && !position.is_game_over() {
+            let (key, mirrored) = self.table_key(&position);
+            let Some(col) = self
+                .table
+                .probe(key)
+                .and_then(|entry| entry.best_move())
+                .map(|col| orient(&position, mirrored, col))
+                .filter(|&col| position.can_play(col))
+            else {
+                break;
+            };
+            position.play(col);
+            variation.push(col);
+        }
+        variation
+    }
+
+    /// Scores every root move to `depth` and returns the best one with its score.
+    ///
+    /// Returns `None` if the root has no moves or the search ran out of budget.
+    fn search_root(&mut self, root: &Position, depth: usize) -> Option<(Coordinate, i32)> {
+        let mut best_score = i32::MIN;
+        let mut best_column = None;
+
+        for i in 0..self.column_order.len() {
+            let col = self.column_order[i];
+            if !root.can_play(col) {
+                continue;
+            }
+            let mut child = *root;
+            child.play(col);
+            // Only a strictly better score can replace the current best, so later
+            // moves are searched with the best score so far as the lower bound.
+            let score = self.minimax(&child, depth, best_score, i32::MAX, false);
+            if self.budget.aborted() {
+                return None;
+            }
+            if best_column.is_none() || score > best_score {
+                best_score = score;
+                best_column = Some(col);
+            }
+        }
+
+        best_column.map(|col| (col, best_score))
+    }
+
+    fn minimax(
+        &mut self,
+        position: &Position,
+        depth: usize,
+        mut alpha: i32,
+        mut beta: i32,
+        is_maximizing: bool,
+    ) -> i32 {
+        if self.budget.visit() {
+            return 0;
+        }
+        if depth == 0 || position.is_game_over() {
+            return self.evaluate(position);
+        }
+
+        let (key, mirrored) = self.table_key(position);
+        let mut table_move = None;
+        if let Some(entry) = self.table.probe(key) {
+            table_move = entry.best_move().map(|col| orient(position, mirrored, col));
+            if entry.depth as usize >= depth {
+                match entry.bound {
+                    Bound::Exact => return entry.score,
+                    Bound::Lower => alpha = alpha.max(entry.score),
+                    Bound::Upper => beta = beta.min(entry.score),
+                    Bound::Empty => {}
+                }
+                if beta <= alpha {
+                    return entry.score;
+                }
+            }
+        }
+        let (alpha_orig, beta_orig) = (alpha, beta);
+
+        // Try the stored best move first, then the usual center-first order
+        let order: Vec<Coordinate> = table_move
+            .into_iter()
+            .chain(
+                self.column_order
+                    .iter()
+                    .copied()
+                    .filter(|&col| Some(col) != table_move),
+            )
+            .filter(|&col| position.can_play(col))
+            .collect();
+
+        let mut best = if is_maximizing { i32::MIN } else { i32::MAX };
+        let mut best_move = None;
+        for col in order {
+            let mut child = *position;
+            child.play(col);
+            let eval = self.minimax(&child, depth - 1, alpha, beta, !is_maximizing);
+            if self.budget.aborted() {
+                return 0;
+            }
+            if is_maximizing {
+                if best_move.is_none() || eval > best {
+                    best = eval;
+                    best_move = Some(col);
+                }
+                alpha = alpha.max(eval);
+            } else {
+                if best_move.is_none() || eval < best {
+                    best = eval;
+                    best_move = Some(col);
+                }
+                beta = beta.min(eval);
+            }
+            if beta <= alpha {
+                break;
+            }
+        }
+
+        let bound = if best <= alpha_orig {
+            Bound::Upper
+        } else if best >= beta_orig {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        let best_move = best_move.map(|col| orient(position, mirrored, col));
+        self.table.store(key, depth, best, bound, best_move);
+        best
+    }
+
+    /// The table key of a position, and whether its entry is stored for the
+    /// mirror image. A position and its mirror image share an entry when the
+    /// evaluator scores them alike, with the move stored for the orientation
+    /// with the smaller hash.
+    fn table_key(&self, position: &Position) -> (u64, bool) {
+        let (hash, mirrored) = if self.symmetric {
+            position.canonical_hash()
+        } else {
+            (position.hash(), false)
+        };
+        (hash ^ self.perspective, mirrored)
+    }
+
+    fn evaluate(&self, position: &Position) -> i32 {
+        self.game
+            .evaluator
+            .evaluate_position(self.game, position, self.player)
+    }
+}
+
+/// Maps a column between a position and the orientation its table entry uses.
+fn orient(position: &Position, mirrored: bool, col: Coordinate) -> Coordinate {
+    if mirrored {
+        position.mirror_column(col)
+    } else {
+        col
+    }
+}
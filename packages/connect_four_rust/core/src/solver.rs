@@ -0,0 +1,368 @@
+// core/src/solver.rs
+
+use crate::position::Position;
+use crate::tt::{Bound, TranspositionTable};
+use crate::Coordinate;
+use serde::{Deserialize, Serialize};
+
+/// The game-theoretic value of a position for the player to move.
+///
+/// `moves` counts the plies, by both players, until the game ends with perfect
+/// play, including the final move.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win { moves: usize },
+    Loss { moves: usize },
+    Draw,
+}
+
+/// An exact solver for gravity positions that fit in a `Position`.
+///
+/// Scores follow the usual convention: a win is worth one point for every cell the
+/// winner did not need to fill, so faster wins score higher, a loss is the negated
+/// value for the opponent, and a draw is 0. The search is a null-window negamax
+/// with alpha-beta pruning, threat-based move ordering and a transposition table
+/// that keeps its contents between calls.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    table: TranspositionTable,
+    nodes: u64,
+    node_limit: Option<u64>,
+    aborted: bool,
+}
+
+impl Solver {
+    /// Creates a solver with the default transposition table size.
+    pub fn new() -> Self {
+        Self::with_memory(TranspositionTable::DEFAULT_MEMORY)
+    }
+
+    /// Creates a solver whose transposition table uses at most `bytes`.
+    pub fn with_memory(bytes: usize) -> Self {
+        Solver {
+            table: TranspositionTable::with_memory(bytes),
+            nodes: 0,
+            node_limit: None,
+            aborted: false,
+        }
+    }
+
+    /// Number of nodes visited since the solver was created.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Forgets every cached result.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Returns the exact score of a position that is not already over.
+    pub fn score(&mut self, position: &Position) -> i32 {
+        let cells = (position.width() * position.height()) as i32;
+        let moves = position.moves() as i32;
+        if position.can_win_next() {
+            return (cells + 1 - moves) / 2;
+        }
+        if position.is_full() {
+            return 0;
+        }
+
+        // Narrow the score range with null-window searches around the midpoint,
+        // biased towards 0 where most positions end up.
+        let mut min = -(cells - moves) / 2;
+        let mut max = (cells + 1 - moves) / 2;
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let result = self.negamax(position, med, med + 1);
+            if self.aborted {
+                return 0;
+            }
+            if result <= med {
+                max = result;
+            } else {
+                min = result;
+            }
+        }
+        min
+    }
+
+    /// Returns the outcome of a position with perfect play from both sides.
+    pub fn solve(&mut self, position: &Position) -> Outcome {
+        if position.winner().is_some() {
+            return Outcome::Loss { moves: 0 };
+        }
+        if position.is_full() {
+            return Outcome::Draw;
+        }
+        let score = self.score(position);
+        outcome_from_score(position, score)
+    }
+
+    /// Like `solve`, but gives up and returns `None` after visiting `max_nodes`
+    /// nodes. Results found before giving up stay in the table.
+    pub fn solve_within(&mut self, position: &Position, max_nodes: u64) -> Option<Outcome> {
+        self.node_limit = Some(self.nodes.saturating_add(max_nodes));
+        self.aborted = false;
+        let outcome = self.solve(position);
+        self.node_limit = None;
+        (!std::mem::take(&mut self.aborted)).then_some(outcome)
+    }
+
+    /// Returns a move that achieves the exact score of the position, preferring
+    /// central columns on ties.
+    ///
+    /// Returns `None` if the position is over.
+    pub fn best_move(&mut self, position: &Position) -> Option<Coordinate> {
+        if position.is_game_over() {
+            return None;
+        }
+        let order: Vec<Coordinate> = column_order(position.width())
+            .filter(|&col| position.can_play(col))
+            .collect();
+        if let Some(&col) = order.iter().find(|&&col| position.is_winning_move(col)) {
+            return Some(col);
+        }
+
+        // Every move loses at once; any of them will do
+        let next = position.non_losing_moves();
+        if next == 0 {
+            return order.first().copied();
+        }
+
+        // One null-window search per move is enough to find one that keeps the score
+        let target = self.score(position);
+        let mut fallback = None;
+        for &col in &order {
+            let stone = next & position.column_mask(col);
+            if stone == 0 {
+                continue;
+            }
+            fallback.get_or_insert(col);
+            let mut child = *position;
+            child.play_stone(stone);
+            if -self.negamax(&child, -target, -target + 1) >= target {
+                return Some(col);
+            }
+        }
+        fallback
+    }
+
+    /// Like `best_move`, but gives up and returns `None` after visiting
+    /// `max_nodes` nodes. Results found before giving up stay in the table.
+    pub fn best_move_within(&mut self, position: &Position, max_nodes: u64) -> Option<Coordinate> {
+        self.node_limit = Some(self.nodes.saturating_add(max_nodes));
+        self.aborted = false;
+        let col = self.best_move(position);
+        self.node_limit = None;
+        col.filter(|_| !std::mem::take(&mut self.aborted))
+    }
+
+    /// Null-window capable negamax. The current player must not be able to win
+    /// with their next move.
+    fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.node_limit.is_some_and(|limit| self.nodes > limit) {
+            self.aborted = true;
+            return 0;
+        }
+        let cells = (position.width() * position.height()) as i32;
+        let moves = position.moves() as i32;
+
+        let next = position.non_losing_moves();
+        if next == 0 {
+            return -(cells - moves) / 2;
+        }
+        if moves >= cells - 2 {
+            return 0;
+        }
+
+        // The opponent cannot win on their next move, so our score has a floor
+        let min = -(cells - 2 - moves) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        // We cannot win on this move, so our score has a ceiling
+        let max = (cells - 1 - moves) / 2;
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        // Mirror images have the same score, so they share an entry
+        let key = position.canonical_hash().0;
+        if let Some(entry) = self.table.probe(key) {
+            match entry.bound {
+                Bound::Lower if entry.score > alpha => {
+                    alpha = entry.score;
+                    if alpha >= beta {
+                        return alpha;
+                    }
+                }
+                Bound::Upper if entry.score < beta => {
+                    beta = entry.score;
+                    if alpha >= beta {
+                        return beta;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Order moves by how many threats they create, then by centrality
+        let mut candidates: Vec<(u64, u32)> = column_order(position.width())
+            .filter_map(|col| {
+                let stone = next & position.column_mask(col);
+                (stone != 0).then(|| (stone, position.move_score(stone)))
+            })
+            .collect();
+        candidates.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+
+        for (stone, _) in candidates {
+            let mut child = *position;
+            child.play_stone(stone);
+            let score = -self.negamax(&child, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                self.table.store(key, 0, score, Bound::Lower, None);
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        self.table.store(key, 0, alpha, Bound::Upper, None);
+        alpha
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts an exact score for the player to move into an `Outcome`.
+pub(crate) fn outcome_from_score(position: &Position, score: i32) -> Outcome {
+    let cells = (position.width() * position.height()) as i32;
+    let moves = position.moves() as i32;
+    if score > 0 {
+        // The player to move wins on their (k + 1)-th stone from here
+        let k = (cells + 1 - moves) / 2 - score;
+        Outcome::Win {
+            moves: (2 * k + 1) as usize,
+        }
+    } else if score < 0 {
+        // The opponent wins on their (k + 1)-th stone from here
+        let k = (cells - moves) / 2 + score;
+        Outcome::Loss {
+            moves: (2 * k + 2) as usize,
+        }
+    } else {
+        Outcome::Draw
+    }
+}
+
+/// Columns ordered from the center outwards.
+fn column_order(width: usize) -> impl Iterator<Item = Coordinate> {
+    let center = width / 2;
+    let mut order: Vec<Coordinate> = (0..width).collect();
+    order.sort_by_key(|&col| (center as isize - col as isize).abs());
+    order.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(moves: &str) -> Position {
+        let mut position = Position::new(7, 6, 4).unwrap();
+        for ch in moves.chars() {
+            position.play(ch.to_digit(10).unwrap() as usize - 1);
+        }
+        position
+    }
+
+    #[test]
+    fn test_immediate_win() {
+        let mut solver = Solver::with_memory(1 << 20);
+        let position = position("121212");
+        assert_eq!(solver.solve(&position), Outcome::Win { moves: 1 });
+        assert_eq!(solver.best_move(&position), Some(0));
+    }
+
+    #[test]
+    fn test_best_move_keeps_score() {
+        let mut solver = Solver::with_memory(1 << 20);
+        let position = position("43443534536656");
+        let score = solver.score(&position);
+        let col = solver.best_move(&position).unwrap();
+        let mut child = position;
+        child.play(col);
+        assert_eq!(-solver.score(&child), score);
+    }
+
+    #[test]
+    fn test_forced_loss() {
+        let mut solver = Solver::with_memory(1 << 20);
+        // Red threatens both ends of an open three on the bottom row
+        let position = position("44553");
+        assert_eq!(solver.solve(&position), Outcome::Loss { moves: 2 });
+    }
+
+    #[test]
+    fn test_known_scores() {
+        // End-game positions with scores checked against an exhaustive search
+        let cases = [
+            ("2252576253462244111563365343671351441", -1),
+            ("7422341735647741166133573473242566", 1),
+            ("23163416124767223154467471272416755633", 0),
+            ("65214673556155731566316327373221417", -1),
+        ];
+        let mut solver = Solver::with_memory(1 << 20);
+        for (moves, expected) in cases {
+            assert_eq!(solver.score(&position(moves)), expected, "{}", moves);
+        }
+    }
+
+    #[test]
+    fn test_solve_within() {
+        let mut solver = Solver::with_memory(1 << 20);
+        assert_eq!(solver.solve_within(&position(""), 1000), None);
+        assert_eq!(
+            solver.solve_within(&position("44553"), 1000),
+            Some(Outcome::Loss { moves: 2 })
+        );
+        // Aborted searches leave nothing wrong behind
+        let position = position("43443534536656");
+        let score = Solver::with_memory(1 << 20).score(&position);
+        assert_eq!(solver.solve_within(&position, 10), None);
+        assert_eq!(solver.best_move_within(&position, 10), None);
+        assert_eq!(solver.score(&position), score);
+        assert!(solver.best_move_within(&position, 1_000_000).is_some());
+    }
+
+    #[test]
+    fn test_outcome_move_counts() {
+        let position = position("");
+        assert_eq!(outcome_from_score(&position, 18), Outcome::Win { moves: 7 });
+        assert_eq!(
+            outcome_from_score(&position, -18),
+            Outcome::Loss { moves: 8 }
+        );
+        assert_eq!(outcome_from_score(&position, 1), Outcome::Win { moves: 41 });
+    }
+}
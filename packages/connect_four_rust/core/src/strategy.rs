@@ -0,0 +1,145 @@
+// core/src/strategy.rs
+
+use crate::{ConnectFour, Coordinate, GameState, MctsStrategy, Quality, Variant};
+
+/// Something that picks moves for the computer.
+///
+/// The built-in opponents behind each `Quality` are implementations of this
+/// trait, and `Quality::strategy` boxes the one for a level. Strategies may keep
+/// state between moves, e.g. a search tree or a random number generator.
+pub trait Strategy {
+    /// Picks a column for the player to move. `state` is not over and has at
+    /// least one playable column.
+    fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate;
+}
+
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate {
+        (**self).choose_move(game, state)
+    }
+}
+
+impl Quality {
+    /// Returns the strategy used for this level.
+    pub fn strategy(self) -> Box<dyn Strategy> {
+        match self {
+            Quality::Bad => Box::new(RandomStrategy),
+            Quality::Medium => Box::new(TacticalStrategy),
+            Quality::Best => Box::new(MinimaxStrategy),
+            Quality::Perfect => Box::new(SolverStrategy),
+            Quality::Mcts => Box::new(MctsStrategy::default()),
+        }
+    }
+}
+
+/// Plays a random column. Used by `Quality::Bad`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomStrategy;
+
+impl Strategy for RandomStrategy {
+    fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate {
+        game.get_random_column(&game.get_valid_columns(&state.board))
+    }
+}
+
+/// Wins if it can, blocks the opponent's immediate win, and otherwise plays a
+/// random column. In `Variant::Misere` it plays a random column that does not
+/// complete one of its own lines. Used by `Quality::Medium`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TacticalStrategy;
+
+impl Strategy for TacticalStrategy {
+    fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate {
+        let tactics = game.tactics(state);
+        let valid_columns = game.get_valid_columns(&state.board);
+
+        // In misère a completed line loses, so stay out of the player's own
+        // winning cells and leave the opponent's open
+        if game.variant() == Variant::Misere {
+            let own_lines = &tactics.player(state.current_player).immediate_wins;
+            let safe: Vec<Coordinate> = valid_columns
+                .iter()
+                .copied()
+                .filter(|col| !own_lines.contains(col))
+                .collect();
+            if !safe.is_empty() {
+                return game.get_random_column(&safe);
+            }
+            return game.get_random_column(&valid_columns);
+        }
+
+        // Try to win in the next move, else block the opponent's winning move
+        if let Some(&col) = tactics.player(state.current_player).immediate_wins.first() {
+            return col;
+        }
+        if let Some(&col) = tactics.forced_blocks.first() {
+            return col;
+        }
+
+        // Else, pick a random column
+        game.get_random_column(&valid_columns)
+    }
+}
+
+/// Plays from the opening book, then searches to the game's `max_depth` with
+/// alpha-beta pruning. Used by `Quality::Best`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinimaxStrategy;
+
+impl Strategy for MinimaxStrategy {
+    fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate {
+        game.get_book_move(state)
+            .unwrap_or_else(|| game.get_best_move(state))
+    }
+}
+
+/// Plays from the opening book, then solves the position exactly. Positions the
+/// solver cannot finish within its node budget are searched like
+/// `MinimaxStrategy` instead. Used by `Quality::Perfect`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolverStrategy;
+
+impl Strategy for SolverStrategy {
+    fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate {
+        game.get_book_move(state)
+            .unwrap_or_else(|| game.get_perfect_move(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    /// Always plays the leftmost open column.
+    struct Leftmost;
+
+    impl Strategy for Leftmost {
+        fn choose_move(&mut self, game: &ConnectFour, state: &GameState) -> Coordinate {
+            (0..game.num_columns())
+                .find(|&col| game.try_place_piece(state, col).is_ok())
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_custom_strategy() {
+        let game = ConnectFour::new();
+        let mut players: [Box<dyn Strategy>; 2] = [Box::new(Leftmost), Quality::Best.strategy()];
+        let mut state = game.create_initial_state();
+        while !state.is_game_over {
+            let player = &mut players[state.moves.len() % 2];
+            let col = player.choose_move(&game, &state);
+            state = game.try_place_piece(&state, col).unwrap();
+        }
+        // Stacking the first column is easy to block and to beat
+        assert_eq!(state.winner, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn test_tactical_blocks() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("17273").unwrap();
+        assert_eq!(TacticalStrategy.choose_move(&game, &state), 3);
+    }
+}
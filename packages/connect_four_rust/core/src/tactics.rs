@@ -0,0 +1,235 @@
+// core/src/tactics.rs
+
+use crate::topology::DIRECTIONS;
+use crate::{Board, Cell, Color, ConnectFour, Coordinate, GameState};
+use serde::{Deserialize, Serialize};
+
+/// An empty cell that would complete a line for a player, in board coordinates
+/// with row 0 at the top.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threat {
+    pub column: Coordinate,
+    pub row: usize,
+}
+
+/// The tactical features of a position for one player.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlayerTactics {
+    pub player: Color,
+    /// Columns where the player would win at once if it were their turn.
+    pub immediate_wins: Vec<Coordinate>,
+    /// Columns that would leave the player with two wins the opponent cannot
+    /// both stop: two playable winning cells, or one directly below another.
+    pub double_threats: Vec<Coordinate>,
+    /// Threats on odd rows, counting the bottom row as row 1.
+    pub odd_threats: Vec<Threat>,
+    /// Threats on even rows, counting the bottom row as row 1.
+    pub even_threats: Vec<Threat>,
+}
+
+/// The tactical features of a position, as returned by `ConnectFour::tactics`.
+///
+/// In standard connect four the first player usually wins the endgame with an
+/// odd threat and the second player with an even one, which is what the threat
+/// parity lists are for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Tactics {
+    /// Columns the player to move must play to stop an opponent's immediate
+    /// win. With more than two players the next player's wins come first.
+    pub forced_blocks: Vec<Coordinate>,
+    pub red: PlayerTactics,
+    pub yellow: PlayerTactics,
+    /// The features of the players after red and yellow, in turn order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub others: Vec<PlayerTactics>,
+}
+
+impl Tactics {
+    /// The features for one player.
+    ///
+    /// Panics if the player is not in the game.
+    pub fn player(&self, player: Color) -> &PlayerTactics {
+        match player {
+            Color::Red => &self.red,
+            Color::Yellow => &self.yellow,
+            Color::Green | Color::Blue => self
+                .others
+                .iter()
+                .find(|tactics| tactics.player == player)
+                .expect("player is not in the game"),
+        }
+    }
+}
+
+impl ConnectFour {
+    /// Lists the immediate wins, forced blocks, double threats and threat parity
+    /// of a position. Works for any board size.
+    pub fn tactics(&self, state: &GameState) -> Tactics {
+        let mut players: Vec<PlayerTactics> = Color::ALL[..self.num_players]
+            .iter()
+            .map(|&player| self.player_tactics(&state.board, player))
+            .collect();
+
+        let mut forced_blocks = Vec::new();
+        let mut opponent = state.current_player.next(self.num_players);
+        while opponent != state.current_player {
+            for &col in &players[opponent.index()].immediate_wins {
+                if !forced_blocks.contains(&col) {
+                    forced_blocks.push(col);
+                }
+            }
+            opponent = opponent.next(self.num_players);
+        }
+
+        let others = players.split_off(2);
+        let yellow = players.pop().expect("games have at least two players");
+        let red = players.pop().expect("games have at least two players");
+        Tactics {
+            forced_blocks,
+            red,
+            yellow,
+            others,
+        }
+    }
+
+    fn player_tactics(&self, board: &Board, player: Color) -> PlayerTactics {
+        let immediate_wins = self.immediate_wins(board, player);
+
+        let mut double_threats = Vec::new();
+        let mut board = board.clone();
+        for col in self.get_valid_columns(&board) {
+            if immediate_wins.contains(&col) {
+                continue;
+            }
+            let row = self.drop_row(&board, col);
+            board[col][row] = Cell::Filled(player);
+            let wins = self.immediate_wins(&board, player);
+            let stacked = wins.iter().any(|&win| {
+                let below = self.drop_row(&board, win);
+                below > 0 && self.completes_line(&board, win, below - 1, player)
+            });
+            if wins.len() >= 2 || stacked {
+                double_threats.push(col);
+            }
+            board[col][row] = Cell::Empty;
+        }
+
+        let mut odd_threats = Vec::new();
+        let mut even_threats = Vec::new();
+        for column in 0..self.num_columns {
+            for row in 0..self.num_rows {
+                if board[column][row] == Cell::Empty
+                    && self.completes_line(&board, column, row, player)
+                {
+                    let threat = Threat { column, row };
+                    if (self.num_rows - row) % 2 == 1 {
+                        odd_threats.push(threat);
+                    } else {
+                        even_threats.push(threat);
+                    }
+                }
+            }
+        }
+
+        PlayerTactics {
+            player,
+            immediate_wins,
+            double_threats,
+            odd_threats,
+            even_threats,
+        }
+    }
+
+    /// Columns whose next free cell completes a line for the player.
+    fn immediate_wins(&self, board: &Board, player: Color) -> Vec<Coordinate> {
+        self.get_valid_columns(board)
+            .into_iter()
+            .filter(|&col| self.completes_line(board, col, self.drop_row(board, col), player))
+            .collect()
+    }
+
+    /// The row a piece dropped in a column that is not full lands on.
+    fn drop_row(&self, board: &Board, col: Coordinate) -> usize {
+        board[col]
+            .iter()
+            .rposition(|&cell| cell == Cell::Empty)
+            .expect("column is not full")
+    }
+
+    /// Returns true if a piece of `player` at the cell would complete a line,
+    /// or, for a cell that already holds one, if it is part of a line.
+    pub(crate) fn completes_line(
+        &self,
+        board: &Board,
+        col: Coordinate,
+        row: usize,
+        player: Color,
+    ) -> bool {
+        let piece = Cell::Filled(player);
+        let count = |dc: isize, dr: isize| {
+            let mut count = 0;
+            let mut next = self.neighbor(col, row, dc, dr);
+            while let Some((c, r)) = next {
+                if (c, r) == (col, row) || board[c][r] != piece {
+                    break;
+                }
+                count += 1;
+                next = self.neighbor(c, r, dc, dr);
+            }
+            count
+        };
+        DIRECTIONS.iter().any(|&(dc, dr)| {
+            // Around a cylinder both counts can reach the same pieces of a row
+            let cells = if dr == 0 {
+                self.num_columns
+            } else {
+                self.num_rows
+            };
+            (1 + count(dc, dr) + count(-dc, -dr)).min(cells) >= self.winning_length
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wins_and_blocks() {
+        let game = ConnectFour::new();
+        let state = game.state_from_moves("171717").unwrap();
+        let tactics = game.tactics(&state);
+        assert_eq!(tactics.red.immediate_wins, vec![0]);
+        assert_eq!(tactics.yellow.immediate_wins, vec![6]);
+        assert_eq!(tactics.forced_blocks, vec![6]);
+
+        let state = game.state_from_moves("1717172").unwrap();
+        assert_eq!(game.tactics(&state).forced_blocks, vec![0]);
+    }
+
+    #[test]
+    fn test_double_threat() {
+        let game = ConnectFour::new();
+        // Red to move can open the three on the bottom row at both ends
+        let state = game.state_from_moves("3344").unwrap();
+        let tactics = game.tactics(&state);
+        assert_eq!(tactics.red.double_threats, vec![1, 4]);
+        assert!(tactics.red.immediate_wins.is_empty());
+    }
+
+    #[test]
+    fn test_threat_parity() {
+        let game = ConnectFour::new();
+        let state = game.state_from_fen("7/7/7/y6/y6/yrr1r2 r").unwrap();
+        let tactics = game.tactics(&state);
+        assert_eq!(tactics.red.odd_threats, vec![Threat { column: 3, row: 5 }]);
+        assert!(tactics.red.even_threats.is_empty());
+        assert!(tactics.yellow.odd_threats.is_empty());
+        assert_eq!(
+            tactics.player(Color::Yellow).even_threats,
+            vec![Threat { column: 0, row: 2 }]
+        );
+        assert_eq!(tactics.red.immediate_wins, vec![3]);
+        assert_eq!(tactics.forced_blocks, vec![0]);
+    }
+}
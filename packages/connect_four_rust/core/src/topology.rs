@@ -0,0 +1,152 @@
+// core/src/topology.rs
+
+use crate::Coordinate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// The four directions a line can run in, as column and row steps.
+pub(crate) const DIRECTIONS: [(isize, isize); 4] = [
+    (1, 0),  // Horizontal
+    (0, 1),  // Vertical
+    (1, 1),  // Diagonal down-right
+    (1, -1), // Diagonal up-right
+];
+
+/// How the edges of the board connect.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// The usual board: lines stop at every edge.
+    #[default]
+    Flat,
+    /// The left and right edges are joined, so horizontal and diagonal lines
+    /// can wrap around from the last column to the first.
+    Cylinder,
+}
+
+impl Topology {
+    /// Maps a cell given by possibly off-board coordinates to the board cell it
+    /// stands for, or `None` if it is off the board.
+    pub fn cell(
+        self,
+        num_columns: usize,
+        num_rows: usize,
+        col: isize,
+        row: isize,
+    ) -> Option<(Coordinate, usize)> {
+        if row < 0 || row >= num_rows as isize {
+            return None;
+        }
+        match self {
+            Topology::Flat if col < 0 || col >= num_columns as isize => None,
+            Topology::Flat => Some((col as Coordinate, row as usize)),
+            Topology::Cylinder => Some((
+                col.rem_euclid(num_columns as isize) as Coordinate,
+                row as usize,
+            )),
+        }
+    }
+
+    /// Every line of `length` distinct cells on the board, as `(column, row)`
+    /// pairs with row 0 at the top. A line that wraps all the way around a
+    /// cylinder is listed once.
+    pub fn lines(
+        self,
+        num_columns: usize,
+        num_rows: usize,
+        length: usize,
+    ) -> Vec<Vec<(Coordinate, usize)>> {
+        let mut lines = Vec::new();
+        let mut seen = HashSet::new();
+        for col in 0..num_columns as isize {
+            for row in 0..num_rows as isize {
+                for &(dc, dr) in &DIRECTIONS {
+                    let line: Option<Vec<_>> = (0..length as isize)
+                        .map(|i| self.cell(num_columns, num_rows, col + dc * i, row + dr * i))
+                        .collect();
+                    let Some(line) = line else {
+                        continue;
+                    };
+                    let mut cells = line.clone();
+                    cells.sort_unstable();
+                    cells.dedup();
+                    if cells.len() == length && seen.insert(cells) {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ConnectFour, ConnectFourConfig, Move, Position, Quality};
+
+    #[test]
+    fn test_line_counts() {
+        // The standard board has 69 lines of four
+        assert_eq!(Topology::Flat.lines(7, 6, 4).len(), 69);
+        // On a cylinder every row has 7 horizontal lines and every diagonal start
+        // column works, so 42 + 21 + 21 + 21
+        assert_eq!(Topology::Cylinder.lines(7, 6, 4).len(), 105);
+        // A line as long as the board is wide is one line per row, not seven
+        assert_eq!(Topology::Cylinder.lines(4, 1, 4).len(), 1);
+        assert!(Topology::Cylinder.lines(3, 1, 4).is_empty());
+    }
+
+    #[test]
+    fn test_cell_wraps() {
+        assert_eq!(Topology::Cylinder.cell(7, 6, -1, 2), Some((6, 2)));
+        assert_eq!(Topology::Cylinder.cell(7, 6, 7, 2), Some((0, 2)));
+        assert_eq!(Topology::Cylinder.cell(7, 6, 0, 6), None);
+        assert_eq!(Topology::Flat.cell(7, 6, -1, 2), None);
+    }
+
+    #[test]
+    fn test_wrapped_win() {
+        let game = ConnectFourConfig::new()
+            .topology(Topology::Cylinder)
+            .build()
+            .unwrap();
+        // Red's bottom row runs from the last column around to the third
+        let state = game.state_from_moves("7711223").unwrap();
+        assert_eq!(state.winner, Some(Color::Red));
+        assert_eq!(
+            game.find_winning_lines(&state.board, Color::Red),
+            vec![vec![(6, 5), (0, 5), (1, 5), (2, 5)]]
+        );
+        let flat = ConnectFour::new().state_from_moves("7711223").unwrap();
+        assert_eq!(flat.winner, None);
+
+        // Boards too large for a bitboard take the board-based path
+        let large = ConnectFourConfig::new()
+            .num_columns(10)
+            .num_rows(8)
+            .topology(Topology::Cylinder)
+            .build()
+            .unwrap();
+        let state = large.state_from_moves("aa11223").unwrap();
+        assert_eq!(state.winner, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_search_blocks_across_seam() {
+        let game = ConnectFourConfig::new()
+            .topology(Topology::Cylinder)
+            .build()
+            .unwrap();
+        // Red threatens to complete the last, first, second and third columns
+        let state = game.state_from_moves("76112").unwrap();
+        assert_eq!(game.tactics(&state).forced_blocks, vec![2]);
+        assert_eq!(game.get_computer_move(&state, Quality::Best), Move::Drop(2));
+
+        // The solver's move pruning sees the threat too
+        let position = Position::from_state(&game, &state).unwrap();
+        assert_eq!(
+            position.non_losing_moves(),
+            position.possible() & position.column_mask(2)
+        );
+    }
+}
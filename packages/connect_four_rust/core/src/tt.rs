@@ -0,0 +1,156 @@
+// core/src/tt.rs
+
+use crate::Coordinate;
+
+/// How a stored score relates to the true value of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Bound {
+    #[default]
+    Empty,
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// A single transposition table slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Entry {
+    pub key: u64,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    best_move: u8,
+}
+
+impl Entry {
+    /// The best move found when the entry was stored, if any.
+    pub fn best_move(&self) -> Option<Coordinate> {
+        match self.best_move {
+            NO_MOVE => None,
+            col => Some(col as Coordinate),
+        }
+    }
+}
+
+const NO_MOVE: u8 = u8::MAX;
+
+/// A fixed-size, always-indexed transposition table keyed by position hashes.
+///
+/// The table is allocated lazily on the first store, so an unused table costs
+/// nothing. Entries are replaced unless the slot already holds a deeper result for
+/// the same key.
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+    capacity: usize,
+}
+
+impl TranspositionTable {
+    /// The memory budget used by `ConnectFour::new`.
+    pub const DEFAULT_MEMORY: usize = 8 << 20;
+
+    /// Creates a table that uses at most `bytes` of memory (at least one entry).
+    pub fn with_memory(bytes: usize) -> Self {
+        TranspositionTable {
+            entries: Vec::new(),
+            capacity: (bytes / std::mem::size_of::<Entry>()).max(1),
+        }
+    }
+
+    /// Number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Memory used by the table once allocated, in bytes.
+    pub fn memory(&self) -> usize {
+        self.capacity * std::mem::size_of::<Entry>()
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Looks up the entry for a key.
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries
+            .get(self.index(key))
+            .filter(|entry| entry.bound != Bound::Empty && entry.key == key)
+            .copied()
+    }
+
+    /// Stores a search result for a key.
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: usize,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Coordinate>,
+    ) {
+        if self.entries.is_empty() {
+            self.entries = vec![Entry::default(); self.capacity];
+        }
+        let index = self.index(key);
+        let slot = &mut self.entries[index];
+        if slot.bound != Bound::Empty && slot.key == key && slot.depth as usize > depth {
+            return;
+        }
+        *slot = Entry {
+            key,
+            score,
+            depth: depth.min(u8::MAX as usize) as u8,
+            bound,
+            best_move: best_move.map_or(NO_MOVE, |col| col as u8),
+        };
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.capacity as u64) as usize
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::with_memory(Self::DEFAULT_MEMORY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_budget() {
+        let table = TranspositionTable::with_memory(1 << 20);
+        assert!(table.memory() <= 1 << 20);
+        assert_eq!(table.capacity(), (1 << 20) / std::mem::size_of::<Entry>());
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let mut table = TranspositionTable::with_memory(1024);
+        assert_eq!(table.probe(42), None);
+        table.store(42, 3, 17, Bound::Lower, Some(4));
+        let entry = table.probe(42).unwrap();
+        assert_eq!(
+            (entry.score, entry.depth, entry.bound),
+            (17, 3, Bound::Lower)
+        );
+        assert_eq!(entry.best_move(), Some(4));
+        assert_eq!(table.probe(42 + table.capacity() as u64), None);
+    }
+
+    #[test]
+    fn test_keeps_deeper_entry() {
+        let mut table = TranspositionTable::with_memory(1024);
+        table.store(7, 5, 1, Bound::Exact, None);
+        table.store(7, 2, 2, Bound::Exact, Some(0));
+        assert_eq!(table.probe(7).unwrap().score, 1);
+        table.store(7, 6, 3, Bound::Upper, None);
+        assert_eq!(table.probe(7).unwrap().score, 3);
+        table.clear();
+        assert_eq!(table.probe(7), None);
+    }
+}
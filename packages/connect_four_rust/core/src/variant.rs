@@ -0,0 +1,619 @@
+// core/src/variant.rs
+
+use crate::limits::Budget;
+use crate::{
+    Cell, Clock, Color, ConnectFour, Coordinate, GameState, MoveError, Quality, SearchLimits,
+};
+use serde::{Deserialize, Serialize};
+
+/// How many times a position may occur in a PopOut game before it is a draw.
+const REPETITION_LIMIT: usize = 3;
+
+/// How many nodes `get_variant_move` may search before it settles for the best
+/// move of the deepest finished iteration.
+const VARIANT_NODES: u64 = 10_000;
+
+/// Score of a won position in `variant_minimax`, beyond any evaluator score.
+const VARIANT_WIN_SCORE: i32 = 1_000_000_000;
+
+/// The rules a game is played by.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Variant {
+    /// Drop pieces until a line is completed or the board is full.
+    #[default]
+    Standard,
+    /// On their turn a player may instead pop one of their own pieces out of the
+    /// bottom of a column. A pop that completes lines for both players wins for
+    /// the player who popped. A full board is not a draw while the player to
+    /// move can pop; the game is drawn when they cannot, or when the same
+    /// position occurs for the third time.
+    PopOut,
+    /// Pop 10: the board is filled in a setup phase, then players pop their own
+    /// pieces out of the bottom row, keeping those that were part of a line.
+    /// The first player to collect `POP_TEN_TARGET` pieces wins. See
+    /// `GameState::pop_ten` for the phase.
+    PopTen,
+    /// An m,n,k-game: there is no gravity, so each move places a piece in any
+    /// empty cell with `Move::Place`. Covers tic-tac-toe (3x3, three in a row)
+    /// and gomoku (15x15, five in a row).
+    Mnk,
+    /// Misère: the player who completes a line loses, and a full board is a
+    /// draw. Only for two players.
+    Misere,
+}
+
+/// A move of any variant.
+///
+/// Drops serialize as a bare column number, so move lists of standard games
+/// look the same as before pops existed; pops serialize as `{"pop": column}`
+/// and placements as `{"column": column, "row": row}`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum Move {
+    /// Drops a piece into the column.
+    Drop(Coordinate),
+    /// Removes the mover's piece from the bottom of the column; the pieces above
+    /// it fall down one row.
+    Pop {
+        #[serde(rename = "pop")]
+        column: Coordinate,
+    },
+    /// Puts a piece in the cell, with row 0 at the top. Only in `Variant::Mnk`.
+    Place { column: Coordinate, row: usize },
+}
+
+impl Move {
+    /// The column the move is played in.
+    pub fn column(self) -> Coordinate {
+        match self {
+            Move::Drop(column) | Move::Pop { column } | Move::Place { column, .. } => column,
+        }
+    }
+}
+
+impl ConnectFour {
+    /// Plays a move of any kind for the current player.
+    pub fn try_play(&self, state: &GameState, mv: Move) -> Result<GameState, MoveError> {
+        match mv {
+            Move::Place { column, row } => self.try_place_at(state, column, row),
+            _ if self.variant == Variant::PopTen => self.try_play_pop_ten(state, mv),
+            Move::Drop(column) => self.try_place_piece(state, column),
+            Move::Pop { column } => self.try_pop_piece(state, column),
+        }
+    }
+
+    /// Pops the current player's piece out of the bottom of a column. Only
+    /// allowed in `Variant::PopOut` and `Variant::PopTen`.
+    pub fn try_pop_piece(
+        &self,
+        state: &GameState,
+        column: Coordinate,
+    ) -> Result<GameState, MoveError> {
+        match self.variant {
+            Variant::Standard | Variant::Misere | Variant::Mnk => {
+                return Err(MoveError::PopNotAllowed)
+            }
+            Variant::PopOut => {}
+            Variant::PopTen => return self.try_play_pop_ten(state, Move::Pop { column }),
+        }
+        if state.is_game_over {
+            return Err(MoveError::GameOver);
+        }
+        if column >= self.num_columns {
+            return Err(MoveError::ColumnOutOfRange {
+                column,
+                num_columns: self.num_columns,
+            });
+        }
+        if state.board[column].last() != Some(&Cell::Filled(state.current_player)) {
+            return Err(MoveError::CannotPop { column });
+        }
+
+        let mut new_board = state.board.clone();
+        new_board[column].pop();
+        new_board[column].insert(0, Cell::Empty);
+
+        Ok(self.finish_move(state, new_board, Move::Pop { column }))
+    }
+
+    /// Lists every move the player to move may make, drops first.
+    pub fn legal_moves(&self, state: &GameState) -> Vec<Move> {
+        if state.is_game_over {
+            return Vec::new();
+        }
+        match self.variant {
+            Variant::PopTen => return self.pop_ten_moves(state),
+            Variant::Mnk => return self.mnk_moves(state),
+            _ => {}
+        }
+        let mut moves: Vec<Move> = self
+            .get_valid_columns(&state.board)
+            .into_iter()
+            .map(Move::Drop)
+            .collect();
+        if self.variant == Variant::PopOut {
+            moves.extend(
+                (0..self.num_columns)
+                    .filter(|&col| {
+                        state.board[col].last() == Some(&Cell::Filled(state.current_player))
+                    })
+                    .map(|column| Move::Pop { column }),
+            );
+        }
+        moves
+    }
+
+    /// Returns true if a state nobody has won is drawn by the variant's rules.
+    pub(crate) fn is_draw(&self, state: &GameState) -> bool {
+        match self.variant {
+            Variant::Standard | Variant::Misere => self.is_board_full(&state.board),
+            Variant::PopOut => {
+                let can_pop = state
+                    .board
+                    .iter()
+                    .any(|column| column.last() == Some(&Cell::Filled(state.current_player)));
+                (self.is_board_full(&state.board) && !can_pop)
+                    || self.repetitions(state) >= REPETITION_LIMIT
+            }
+            Variant::PopTen => self.legal_moves(state).is_empty(),
+            Variant::Mnk => state
+                .board
+                .iter()
+                .flatten()
+                .all(|&cell| cell != Cell::Empty),
+        }
+    }
+
+    /// Whether every move of the variant drops a piece, so that the column
+    /// strategies and the bitboard search can play it.
+    pub(crate) fn drops_only(&self) -> bool {
+        matches!(self.variant, Variant::Standard | Variant::Misere)
+    }
+
+    /// The moves the AI considers: the legal moves, except that m,n,k games
+    /// only look at cells next to a piece.
+    fn search_moves(&self, state: &GameState) -> Vec<Move> {
+        if self.variant == Variant::Mnk && !state.is_game_over {
+            return self.mnk_candidate_moves(state);
+        }
+        self.legal_moves(state)
+    }
+
+    /// Counts how often the position of `state`, including the player to move,
+    /// has occurred in its recorded history, taking the moves back one at a time
+    /// on a single board.
+    fn repetitions(&self, state: &GameState) -> usize {
+        // Drops add a piece and pops take one away, so only pops allow repeats
+        if !state.moves.iter().any(|mv| matches!(mv, Move::Pop { .. })) {
+            return 1;
+        }
+        let mut count = 1;
+        let mut board = state.board.clone();
+        let mut player = state.current_player;
+        for &mv in state.moves.iter().rev() {
+            player = player.previous(self.num_players);
+            if self.take_back(&mut board, mv, player).is_err() {
+                break;
+            }
+            if player == state.current_player && board == state.board {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Gets the computer's move in the game's variant.
+    ///
+    /// Standard games use the strategy of `quality`. Other variants can't use
+    /// the bitboard search or the solver: `Quality::Bad` plays a random move,
+    /// `Quality::Medium` takes immediate wins and avoids moves that hand the
+    /// opponent one, and the stronger levels search up to `max_depth` moves
+    /// ahead, one depth at a time, until `VARIANT_NODES` nodes are spent. In
+    /// m,n,k games the AI only considers cells next to a piece.
+    pub fn get_variant_move(&self, state: &GameState, quality: Quality) -> Move {
+        if self.variant == Variant::Standard {
+            return self.get_computer_move(state, quality);
+        }
+        let moves = self.legal_moves(state);
+        assert!(!moves.is_empty(), "No legal moves available");
+
+        match quality {
+            Quality::Bad => self.get_random_move(&moves),
+            Quality::Medium => self.get_tactical_move(state, &self.search_moves(state)),
+            Quality::Best | Quality::Perfect | Quality::Mcts => {
+                self.get_best_variant_move(state, self.max_depth, &mut Budget::nodes(VARIANT_NODES))
+            }
+        }
+    }
+
+    /// Gets the computer's best move in the game's variant using iterative
+    /// deepening within the limits, like `get_computer_move_with_limits`.
+    ///
+    /// Without `limits.depth` the search goes as deep as a node or time limit
+    /// allows, or to `max_depth` when neither is set.
+    pub fn get_variant_move_with_limits(
+        &self,
+        state: &GameState,
+        limits: SearchLimits,
+        clock: &dyn Clock,
+    ) -> Move {
+        if self.variant == Variant::Standard {
+            return self.get_computer_move_with_limits(state, limits, clock);
+        }
+        assert!(!state.is_game_over, "No legal moves available");
+        self.get_best_variant_move(
+            state,
+            limits.max_depth(self.max_depth),
+            &mut Budget::new(&limits, clock),
+        )
+    }
+
+    fn get_random_move(&self, moves: &[Move]) -> Move {
+        let index = crate::random::index(&mut self.rng.borrow_mut(), moves.len());
+        moves[index]
+    }
+
+    fn get_tactical_move(&self, state: &GameState, moves: &[Move]) -> Move {
+        let player = state.current_player;
+        // In misère a player who completes a line hands the win to the other
+        let lost = |s: &GameState| s.winner.is_some_and(|winner| winner != player);
+        let mut safe = Vec::new();
+        for &mv in moves {
+            let Ok(next) = self.try_play(state, mv) else {
+                continue;
+            };
+            if next.winner == Some(player) {
+                return mv;
+            }
+            // After a Pop 10 extra turn the replies are the player's own
+            let loses = lost(&next)
+                || (next.current_player != player
+                    && self
+                        .search_moves(&next)
+                        .into_iter()
+                        .any(|reply| self.try_play(&next, reply).is_ok_and(|s| lost(&s))));
+            if !loses {
+                safe.push(mv);
+            }
+        }
+        if safe.is_empty() {
+            self.get_random_move(moves)
+        } else {
+            self.get_random_move(&safe)
+        }
+    }
+
+    /// Searches one depth at a time and returns the best move from the deepest
+    /// iteration that finished within the budget. The first iteration always
+    /// finishes.
+    fn get_best_variant_move(
+        &self,
+        state: &GameState,
+        max_depth: usize,
+        budget: &mut Budget,
+    ) -> Move {
+        // Move ordering: drops before pops, center columns (and rows) first
+        let center = self.num_columns / 2;
+        let center_row = self.num_rows / 2;
+        let mut ordered = self.search_moves(state);
+        ordered.sort_by_key(|&mv| {
+            let row_distance = match mv {
+                Move::Place { row, .. } => (center_row as isize - row as isize).abs(),
+                _ => 0,
+            };
+            (
+                matches!(mv, Move::Pop { .. }),
+                (center as isize - mv.column() as isize).abs() + row_distance,
+            )
+        });
+        // Without pops every move fills a cell, so the game ends within the
+        // empty cells
+        let max_depth = match self.variant {
+            Variant::PopOut | Variant::PopTen => max_depth,
+            _ => max_depth.min(self.empty_cells(&state.board).saturating_sub(1)),
+        };
+
+        let mut best_move = ordered[0];
+        if ordered.len() == 1 {
+            return best_move;
+        }
+        for depth in 0..=max_depth {
+            let Some(mv) = self.search_variant_root(state, &ordered, depth, budget) else {
+                break;
+            };
+            // The next iteration searches the best move first
+            best_move = mv;
+            ordered.retain(|&other| other != mv);
+            ordered.insert(0, mv);
+            budget.allow_abort();
+        }
+        best_move
+    }
+
+    /// Scores every move to `depth` and returns the best one.
+    ///
+    /// Returns `None` if no move could be played or the search ran out of budget.
+    fn search_variant_root(
+        &self,
+        state: &GameState,
+        moves: &[Move],
+        depth: usize,
+        budget: &mut Budget,
+    ) -> Option<Move> {
+        let mut best_score = i32::MIN;
+        let mut best_move = None;
+        for &mv in moves {
+            let Ok(next) = self.try_play(state, mv) else {
+                continue;
+            };
+            let score = self.variant_minimax(
+                &next,
+                depth,
+                best_score,
+                i32::MAX,
+                state.current_player,
+                budget,
+            );
+            if budget.aborted() {
+                return None;
+            }
+            if best_move.is_none() || score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+        }
+        best_move
+    }
+
+    /// Minimax with alpha-beta pruning over the moves of any variant, scoring
+    /// positions for `player`. Wins score beyond any evaluator score, sooner
+    /// wins higher. Players do not always alternate, so whose turn it is comes
+    /// from the state. Returns 0 once the budget runs out.
+    fn variant_minimax(
+        &self,
+        state: &GameState,
+        depth: usize,
+        mut alpha: i32,
+        mut beta: i32,
+        player: Color,
+        budget: &mut Budget,
+    ) -> i32 {
+        if budget.visit() {
+            return 0;
+        }
+        match state.winner {
+            Some(winner) if winner == player => return VARIANT_WIN_SCORE + depth as i32,
+            Some(_) => return -VARIANT_WIN_SCORE - depth as i32,
+            None if state.is_game_over => return 0,
+            None => {}
+        }
+        if depth == 0 {
+            return match self.variant {
+                Variant::PopTen => self.pop_ten_score(state, player),
+                _ => self.evaluator.evaluate(self, &state.board, player),
+            };
+        }
+
+        let is_maximizing = state.current_player == player;
+        let mut best = if is_maximizing { i32::MIN } else { i32::MAX };
+        for mv in self.search_moves(state) {
+            let Ok(next) = self.try_play(state, mv) else {
+                continue;
+            };
+            let eval = self.variant_minimax(&next, depth - 1, alpha, beta, player, budget);
+            if budget.aborted() {
+                return 0;
+            }
+            if is_maximizing {
+                best = best.max(eval);
+                alpha = alpha.max(eval);
+            } else {
+                best = best.min(eval);
+                beta = beta.min(eval);
+            }
+            if beta <= alpha {
+                break;
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectFourConfig, Evaluator, WeightedEvaluator};
+
+    #[test]
+    fn test_pop_shifts_column() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::PopOut)
+            .max_depth(3)
+            .seed(7)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("1112").unwrap();
+        let popped = game.try_pop_piece(&state, 0).unwrap();
+        assert_eq!(popped.board[0][5], Cell::Filled(Color::Yellow));
+        assert_eq!(popped.board[0][4], Cell::Filled(Color::Red));
+        assert_eq!(popped.board[0][3], Cell::Empty);
+        assert_eq!(popped.current_player, Color::Yellow);
+        assert_eq!(popped.moves.last(), Some(&Move::Pop { column: 0 }));
+
+        // Only the mover's own pieces can be popped
+        assert_eq!(
+            game.try_pop_piece(&state, 1),
+            Err(MoveError::CannotPop { column: 1 })
+        );
+        assert_eq!(
+            ConnectFour::new().try_pop_piece(&state, 0),
+            Err(MoveError::PopNotAllowed)
+        );
+
+        let undone = game.undo(&popped).unwrap();
+        assert_eq!(undone.board, state.board);
+        assert_eq!(game.redo(&undone).unwrap(), popped);
+    }
+
+    #[test]
+    fn test_pop_completing_lines() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::PopOut)
+            .max_depth(3)
+            .seed(7)
+            .build()
+            .unwrap();
+        // Popping column 4 completes yellow's bottom row and red's row above it
+        let state = game.state_from_fen("7/7/7/3r3/rrry3/yyyr2y r").unwrap();
+        let popped = game.try_pop_piece(&state, 3).unwrap();
+        assert!(game.check_winner(&popped.board, Color::Yellow));
+        assert_eq!(popped.winner, Some(Color::Red));
+        assert!(popped.is_game_over);
+
+        // A pop that only completes the opponent's line loses
+        let state = game.state_from_fen("7/7/7/7/3y1r1/yyyr1rr r").unwrap();
+        let popped = game.try_pop_piece(&state, 3).unwrap();
+        assert_eq!(popped.winner, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn test_full_board_and_repetition() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::PopOut)
+            .max_depth(3)
+            .seed(7)
+            .build()
+            .unwrap();
+        let full = game
+            .state_from_fen("yryryry/yryryry/ryryryr/ryryryr/yryryry/yryryry r")
+            .unwrap();
+        assert!(!full.is_game_over);
+        assert_eq!(game.legal_moves(&full).len(), 3);
+        // Only pops are left for the AI
+        for quality in [Quality::Bad, Quality::Medium, Quality::Best] {
+            let mv = game.get_computer_move(&full, quality);
+            assert!(matches!(mv, Move::Pop { .. }), "{:?} is not a pop", mv);
+        }
+        let mv = game.get_computer_move_with_limits(
+            &full,
+            SearchLimits::nodes(1),
+            &crate::SystemClock::new(),
+        );
+        assert!(game.try_play(&full, mv).is_ok());
+
+        let state = game.state_from_moves("1234").unwrap();
+        let mut repeated = state.clone();
+        for mv in [
+            Move::Pop { column: 0 },
+            Move::Pop { column: 1 },
+            Move::Drop(0),
+            Move::Drop(1),
+        ]
+        .into_iter()
+        .cycle()
+        .take(8)
+        {
+            assert!(!repeated.is_game_over);
+            repeated = game.try_play(&repeated, mv).unwrap();
+        }
+        assert!(repeated.is_game_over);
+        assert_eq!(repeated.winner, None);
+    }
+
+    #[test]
+    fn test_ai_takes_popout_win() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::PopOut)
+            .max_depth(3)
+            .seed(7)
+            .build()
+            .unwrap();
+        // Yellow's only immediate win is popping column 4
+        let state = game.state_from_fen("7/7/7/3y2r/yyyr2r/ryry1rr y").unwrap();
+        for quality in [Quality::Medium, Quality::Best] {
+            assert_eq!(
+                game.get_variant_move(&state, quality),
+                Move::Pop { column: 3 }
+            );
+        }
+        // Even the first iteration of a limited search sees the win
+        assert_eq!(
+            game.get_variant_move_with_limits(
+                &state,
+                SearchLimits::nodes(1),
+                &crate::SystemClock::new()
+            ),
+            Move::Pop { column: 3 }
+        );
+    }
+
+    #[test]
+    fn test_move_notation_and_serialization() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::PopOut)
+            .max_depth(3)
+            .seed(7)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("1112^1").unwrap();
+        assert_eq!(state.to_move_string(), "1112^1");
+        assert_eq!(
+            serde_json::to_string(&state.moves).unwrap(),
+            r#"[0,0,0,1,{"pop":0}]"#
+        );
+        let moves: Vec<Move> = serde_json::from_str(r#"[3,{"pop":2}]"#).unwrap();
+        assert_eq!(moves, vec![Move::Drop(3), Move::Pop { column: 2 }]);
+    }
+
+    #[test]
+    fn test_misere_line_loses() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::Misere)
+            .max_depth(3)
+            .seed(5)
+            .build()
+            .unwrap();
+        let state = game.state_from_moves("1212121").unwrap();
+        assert_eq!(state.winner, Some(Color::Yellow));
+        assert!(state.is_game_over);
+        assert_eq!(game.try_pop_piece(&state, 0), Err(MoveError::PopNotAllowed));
+
+        let parsed = game.state_from_fen(&state.to_fen()).unwrap();
+        assert_eq!(parsed.winner, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn test_misere_ai_avoids_completing_lines() {
+        let game = ConnectFourConfig::new()
+            .variant(Variant::Misere)
+            .max_depth(3)
+            .seed(5)
+            .build()
+            .unwrap();
+        // Red has three in the first column, which the standard AI would complete
+        let state = game.state_from_moves("121212").unwrap();
+        assert_eq!(
+            ConnectFour::new().get_computer_move(&state, Quality::Best),
+            Move::Drop(0)
+        );
+        for quality in [Quality::Medium, Quality::Best] {
+            assert_ne!(game.get_computer_move(&state, quality), Move::Drop(0));
+            assert_ne!(game.get_variant_move(&state, quality), Move::Drop(0));
+        }
+        // Yellow has two lines to complete, which are no threat to Red
+        let state = game.state_from_moves("121212435343").unwrap();
+        for _ in 0..20 {
+            assert_ne!(
+                game.get_variant_move(&state, Quality::Medium),
+                Move::Drop(0)
+            );
+        }
+
+        let evaluator = WeightedEvaluator::default();
+        let standard = ConnectFour::new();
+        assert_eq!(
+            evaluator.evaluate(&game, &state.board, Color::Red),
+            -evaluator.evaluate(&standard, &state.board, Color::Red)
+        );
+    }
+}
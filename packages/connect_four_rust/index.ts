@@ -37,6 +37,14 @@ export class ConnectFourWasm {
     }
   }
 
+  public static load_opening_book(bytes: Uint8Array): void {
+    ConnectFourWasm.game.load_opening_book(bytes);
+  }
+
+  public static set_seed(seed: bigint): void {
+    ConnectFourWasm.game.set_seed(seed);
+  }
+
   public static create_initial_state(): GameStateJS {
     return ConnectFourWasm.game.create_initial_state();
   }
@@ -48,6 +56,37 @@ export class ConnectFourWasm {
     return ConnectFourWasm.game.place_piece(state_js, column);
   }
 
+  public static pop_piece(state_js: GameStateJS, column: number): GameStateJS {
+    return ConnectFourWasm.game.pop_piece(state_js, column);
+  }
+
+  public static place_at(
+    state_js: GameStateJS,
+    column: number,
+    row: number
+  ): GameStateJS {
+    return ConnectFourWasm.game.place_at(state_js, column, row);
+  }
+
+  public static legal_moves(state_js: GameStateJS): any[] {
+    return ConnectFourWasm.game.legal_moves(state_js);
+  }
+
+  public static get_variant_move(
+    state_js: GameStateJS,
+    quality: string
+  ): number | { pop: number } | { column: number; row: number } {
+    return ConnectFourWasm.game.get_variant_move(state_js, quality);
+  }
+
+  public static undo(state_js: GameStateJS): GameStateJS {
+    return ConnectFourWasm.game.undo(state_js);
+  }
+
+  public static redo(state_js: GameStateJS): GameStateJS {
+    return ConnectFourWasm.game.redo(state_js);
+  }
+
   public static get_current_player(state_js: GameStateJS) {
     return ConnectFourWasm.game.get_current_player(state_js);
   }
@@ -60,9 +99,44 @@ export class ConnectFourWasm {
     return ConnectFourWasm.game.get_winner(state_js);
   }
 
+  public static get_winning_lines(state_js: GameStateJS): [number, number][][] {
+    return ConnectFourWasm.game.get_winning_lines(state_js);
+  }
+
   public static get_computer_move(state_js: GameStateJS, quality: string) {
     return ConnectFourWasm.game.get_computer_move(state_js, quality);
   }
+
+  public static set_quality(quality: string): void {
+    ConnectFourWasm.game.set_quality(quality);
+  }
+
+  public static set_mcts(playouts: number, exploration: number): void {
+    ConnectFourWasm.game.set_mcts(playouts, exploration);
+  }
+
+  public static get_mcts_move_timed(state_js: GameStateJS, budget_ms: number) {
+    return ConnectFourWasm.game.get_mcts_move_timed(state_js, budget_ms);
+  }
+
+  public static analyze(state_js: GameStateJS, budget_ms: number): any[] {
+    return ConnectFourWasm.game.analyze(state_js, budget_ms);
+  }
+
+  public static get_tactics(state_js: GameStateJS): any {
+    return ConnectFourWasm.game.get_tactics(state_js);
+  }
+
+  public static choose_move(state_js: GameStateJS): number {
+    return ConnectFourWasm.game.choose_move(state_js);
+  }
+
+  public static get_computer_move_timed(
+    state_js: GameStateJS,
+    budget_ms: number
+  ) {
+    return ConnectFourWasm.game.get_computer_move_timed(state_js, budget_ms);
+  }
 }
 
 export type { ConnectFour as ConnectFourWasmType };
@@ -4,7 +4,7 @@ use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, Result};
 use actix_web::http::header;
 use serde::{Deserialize, Serialize};
 use tera::{Context, Tera};
-use connect_four_core::{ConnectFour, GameState, Quality, Color};
+use connect_four_core::{ConnectFour, GameState, Quality, Color, Strategy};
 use base64::{encode_config, decode_config, URL_SAFE_NO_PAD};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -27,7 +27,15 @@ impl ExtendedGameState {
     }
 }
 
-async fn index(req: HttpRequest, tmpl: web::Data<Tera>) -> Result<HttpResponse> {
+// Builds the computer opponent for a quality level. Swap this out in `main` to
+// play against a custom strategy.
+type StrategyFactory = fn(Quality) -> Box<dyn Strategy>;
+
+async fn index(
+    req: HttpRequest,
+    tmpl: web::Data<Tera>,
+    strategies: web::Data<StrategyFactory>,
+) -> Result<HttpResponse> {
     let query_string = req.query_string();
     let params: std::collections::HashMap<String, String> =
         serde_urlencoded::from_str(query_string).unwrap_or_default();
@@ -47,11 +55,17 @@ async fn index(req: HttpRequest, tmpl: web::Data<Tera>) -> Result<HttpResponse>
     // If it's the computer's turn, compute the move
     if !game_state.game_state.is_game_over && game_state.game_state.current_player == Color::Yellow {
         let connect_four = ConnectFour::new();
-        let computer_move = connect_four.get_computer_move(
-            &game_state.game_state,
-            game_state.minimax_quality,
-        );
-        game_state.game_state = connect_four.place_piece(&game_state.game_state, computer_move);
+        let mut strategy = (strategies.get_ref())(game_state.minimax_quality);
+        let computer_move = strategy.choose_move(&connect_four, &game_state.game_state);
+        game_state.game_state = match connect_four.try_place_piece(&game_state.game_state, computer_move) {
+            Ok(state) => state,
+            Err(e) => {
+                return Err(actix_web::error::ErrorInternalServerError(format!(
+                    "Invalid computer move {}: {}",
+                    computer_move, e
+                )));
+            }
+        };
         game_state.newest_computer_piece_column = Some(computer_move);
     }
 
@@ -69,6 +83,10 @@ async fn index(req: HttpRequest, tmpl: web::Data<Tera>) -> Result<HttpResponse>
     let quality_links = get_quality_links(&game_state);
     ctx.insert("quality_links", &quality_links);
 
+    // Generate URLs for taking back and replaying moves
+    ctx.insert("undo_link", &get_undo_link(&game_state));
+    ctx.insert("redo_link", &get_redo_link(&game_state));
+
     // Generate board cells with links
     let cells = render_cells(&game_state);
     ctx.insert("cells", &cells);
@@ -100,6 +118,8 @@ fn get_quality_links(game_state: &ExtendedGameState) -> Vec<(String, String, boo
         (Quality::Bad, "Bad"),
         (Quality::Medium, "Medium"),
         (Quality::Best, "Best"),
+        (Quality::Perfect, "Perfect"),
+        (Quality::Mcts, "MCTS"),
     ];
 
     qualities
@@ -115,17 +135,61 @@ fn get_quality_links(game_state: &ExtendedGameState) -> Vec<(String, String, boo
         .collect()
 }
 
+// Takes back moves until it is the player's turn again, so the computer's reply is undone too
+fn get_undo_link(game_state: &ExtendedGameState) -> Option<String> {
+    let connect_four = ConnectFour::new();
+    let mut state = connect_four.undo(&game_state.game_state).ok()?;
+    while state.current_player != Color::Red {
+        state = connect_four.undo(&state).ok()?;
+    }
+
+    let mut new_state = game_state.clone();
+    new_state.game_state = state;
+    new_state.newest_piece_column = None;
+    new_state.newest_computer_piece_column = None;
+    Some(format!("/?state={}", encode_state(&new_state)))
+}
+
+// Replays the player's move and the computer's reply that were undone
+fn get_redo_link(game_state: &ExtendedGameState) -> Option<String> {
+    let connect_four = ConnectFour::new();
+    let mut state = connect_four.redo(&game_state.game_state).ok()?;
+    while state.current_player != Color::Red && !state.undone_moves.is_empty() {
+        state = connect_four.redo(&state).ok()?;
+    }
+
+    let mut new_state = game_state.clone();
+    new_state.game_state = state;
+    new_state.newest_piece_column = None;
+    new_state.newest_computer_piece_column = None;
+    Some(format!("/?state={}", encode_state(&new_state)))
+}
+
 fn render_cells(game_state: &ExtendedGameState) -> Vec<Vec<String>> {
     let connect_four = ConnectFour::new();
     let mut cells = vec![];
 
-    for row in (0..connect_four.num_rows).rev() {
+    // Cells of the winning line(s), highlighted once the game is won
+    let winning_cells: Vec<(usize, usize)> = match game_state.game_state.winner {
+        Some(winner) => connect_four
+            .find_winning_lines(&game_state.game_state.board, winner)
+            .into_iter()
+            .flatten()
+            .collect(),
+        None => vec![],
+    };
+
+    for row in (0..connect_four.num_rows()).rev() {
         let mut row_cells = vec![];
-        for col in 0..connect_four.num_columns {
+        for col in 0..connect_four.num_columns() {
             let cell = &game_state.game_state.board[col][row];
-            let cell_html = if matches!(cell, connect_four_core::Cell::Empty) && !game_state.game_state.is_game_over {
+            let next_state = match cell {
+                connect_four_core::Cell::Empty => connect_four.try_place_piece(&game_state.game_state, col).ok(),
+                _ => None,
+            };
+            let cell_html = if let Some(next_state) = next_state {
                 let mut new_state = game_state.clone();
-                new_state.game_state = connect_four.place_piece(&game_state.game_state, col);
+                new_state.game_state = next_state;
                 new_state.newest_piece_column = Some(col);
                 new_state.newest_computer_piece_column = None;
 
@@ -143,8 +207,10 @@ fn render_cells(game_state: &ExtendedGameState) -> Vec<Vec<String>> {
                     _ => "empty",
                 };
 
-                format!(r#"<div class="cell {class}"><div class="piece {class}"></div></div>"#,
-                    class = class)
+                let winning = if winning_cells.contains(&(col, row)) { " winning" } else { "" };
+
+                format!(r#"<div class="cell {class}{winning}"><div class="piece {class}"></div></div>"#,
+                    class = class, winning = winning)
             };
             row_cells.push(cell_html);
         }
@@ -160,6 +226,7 @@ async fn main() -> std::io::Result<()> {
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(tera.clone()))
+            .app_data(web::Data::new(Quality::strategy as StrategyFactory))
             .route("/", web::get().to(index))
     })
     .bind("127.0.0.1:8080")?
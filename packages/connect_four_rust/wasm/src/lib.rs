@@ -1,16 +1,65 @@
 // wasm/src/lib.rs
 
-use connect_four_core::{Color, ConnectFour as CoreConnectFour, GameState, Quality};
+use connect_four_core::{
+    Clock, Color, ConnectFour as CoreConnectFour, ConnectFourConfig, GameState, MctsStrategy, Move,
+    MoveError, OpeningBook, Quality, SearchLimits, Strategy, Variant,
+};
 use serde_wasm_bindgen::{from_value, to_value};
+use std::cell::RefCell;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue; // Add this line
 
 #[cfg(target_arch = "wasm32")]
 extern crate wasm_bindgen;
 
+/// A search clock backed by the host's `Date.now()`.
+struct JsClock;
+
+impl Clock for JsClock {
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+    }
+}
+
 #[wasm_bindgen]
 pub struct ConnectFour {
     core: CoreConnectFour,
+    strategy: RefCell<Box<dyn Strategy>>,
+}
+
+impl ConnectFour {
+    /// Replaces the strategy used by `choose_move`. Not exported to JavaScript;
+    /// Rust code embedding this crate can plug in its own opponents here.
+    pub fn set_strategy(&mut self, strategy: Box<dyn Strategy>) {
+        self.strategy = RefCell::new(strategy);
+    }
+
+    /// Rejects the column-only computer moves in games without gravity, where
+    /// `get_variant_move` picks a cell instead, and in the variants with pops.
+    fn check_drops(&self) -> Result<(), JsValue> {
+        match self.core.variant() {
+            Variant::Mnk => Err(JsValue::from_str(&format!(
+                "Invalid move: {}",
+                MoveError::DropNotAllowed
+            ))),
+            Variant::PopOut | Variant::PopTen => Err(JsValue::from_str(
+                "Invalid variant: the computer may pop; use get_variant_move",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn parse_quality(quality_str: &str) -> Quality {
+    match quality_str {
+        "bad" => Quality::Bad,
+        "medium" => Quality::Medium,
+        "best" => Quality::Best,
+        "perfect" => Quality::Perfect,
+        "mcts" => Quality::Mcts,
+        _ => Quality::Best,
+    }
 }
 
 #[wasm_bindgen]
@@ -20,9 +69,61 @@ impl ConnectFour {
     pub fn new() -> ConnectFour {
         ConnectFour {
             core: CoreConnectFour::new(),
+            strategy: RefCell::new(Quality::Best.strategy()),
         }
     }
 
+    /// Creates a game with a custom board size and winning line length.
+    #[wasm_bindgen]
+    pub fn with_config(
+        num_columns: usize,
+        num_rows: usize,
+        winning_length: usize,
+    ) -> Result<ConnectFour, JsValue> {
+        let core = ConnectFourConfig::new()
+            .num_columns(num_columns)
+            .num_rows(num_rows)
+            .winning_length(winning_length)
+            .build()
+            .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+        Ok(ConnectFour {
+            core,
+            strategy: RefCell::new(Quality::Best.strategy()),
+        })
+    }
+
+    /// Creates a game from a config object, e.g. `{ num_columns: 8, weights:
+    /// { center: 10 } }`. Missing fields keep their defaults.
+    #[wasm_bindgen]
+    pub fn from_config(config_js: &JsValue) -> Result<ConnectFour, JsValue> {
+        let config: ConnectFourConfig = from_value(config_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+        let core = config
+            .build()
+            .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+        Ok(ConnectFour {
+            core,
+            strategy: RefCell::new(Quality::Best.strategy()),
+        })
+    }
+
+    /// Seeds the random choices of the "bad", "medium" and "mcts" levels and of
+    /// `get_mcts_move_timed`. The same seed gives the same moves as the native
+    /// build.
+    #[wasm_bindgen]
+    pub fn set_seed(&mut self, seed: u64) {
+        self.core.set_seed(seed);
+    }
+
+    /// Loads an opening book in its binary format for the "best" and "perfect" levels.
+    #[wasm_bindgen]
+    pub fn load_opening_book(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let book = OpeningBook::from_bytes(bytes)
+            .map_err(|e| JsValue::from_str(&format!("Invalid opening book: {}", e)))?;
+        self.core.set_opening_book(Some(book));
+        Ok(())
+    }
+
     /// Creates the initial game state and returns it as a JsValue.
     #[wasm_bindgen]
     pub fn create_initial_state(&self) -> Result<JsValue, JsValue> {
@@ -31,15 +132,110 @@ impl ConnectFour {
     }
 
     /// Places a piece on the board and returns the new game state.
+    /// Throws if the column is out of range or full, or the game is over.
     #[wasm_bindgen]
     pub fn place_piece(&self, state_js: &JsValue, coordinate: usize) -> Result<JsValue, JsValue> {
         let state: GameState = from_value(state_js.clone())
             .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
-        let new_state = self.core.place_piece(&state, coordinate);
+        let new_state = self
+            .core
+            .try_place_piece(&state, coordinate)
+            .map_err(|e| JsValue::from_str(&format!("Invalid move: {}", e)))?;
+        to_value(&new_state).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Pops the current player's piece out of the bottom of a column and returns
+    /// the new game state. Throws unless the game is played with PopOut rules
+    /// and the bottom piece belongs to the current player.
+    #[wasm_bindgen]
+    pub fn pop_piece(&self, state_js: &JsValue, column: usize) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        let new_state = self
+            .core
+            .try_pop_piece(&state, column)
+            .map_err(|e| JsValue::from_str(&format!("Invalid move: {}", e)))?;
+        to_value(&new_state).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Places the current player's piece in a cell, with row 0 at the top, and
+    /// returns the new game state. Throws unless the game is played without
+    /// gravity (the m,n,k variant) and the cell is empty.
+    #[wasm_bindgen]
+    pub fn place_at(
+        &self,
+        state_js: &JsValue,
+        column: usize,
+        row: usize,
+    ) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        let new_state = self
+            .core
+            .try_place_at(&state, column, row)
+            .map_err(|e| JsValue::from_str(&format!("Invalid move: {}", e)))?;
+        to_value(&new_state).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Returns the legal moves of the current player: column numbers for drops,
+    /// `{ pop: column }` objects for pops and `{ column, row }` objects for
+    /// placements.
+    #[wasm_bindgen]
+    pub fn legal_moves(&self, state_js: &JsValue) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        to_value(&self.core.legal_moves(&state))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Determines the computer's move in the game's variant, as a column number
+    /// for a drop, a `{ pop: column }` object for a pop or a `{ column, row }`
+    /// object for a placement.
+    #[wasm_bindgen]
+    pub fn get_variant_move(
+        &self,
+        state_js: &JsValue,
+        quality_str: &str,
+    ) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        if state.is_game_over {
+            return Err(JsValue::from_str("Invalid state: the game is already over"));
+        }
+        let mv: Move = self
+            .core
+            .get_variant_move(&state, parse_quality(quality_str));
+        to_value(&mv).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Takes back the last move and returns the new game state.
+    /// Throws if there is nothing to undo.
+    #[wasm_bindgen]
+    pub fn undo(&self, state_js: &JsValue) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        let new_state = self
+            .core
+            .undo(&state)
+            .map_err(|e| JsValue::from_str(&format!("Cannot undo: {}", e)))?;
+        to_value(&new_state).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Replays the last undone move and returns the new game state.
+    /// Throws if there is nothing to redo.
+    #[wasm_bindgen]
+    pub fn redo(&self, state_js: &JsValue) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        let new_state = self
+            .core
+            .redo(&state)
+            .map_err(|e| JsValue::from_str(&format!("Cannot redo: {}", e)))?;
         to_value(&new_state).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
     /// Determines the computer's move based on the quality and returns the column index.
+    /// Throws in games without gravity and with pops; use `get_variant_move` there.
     #[wasm_bindgen]
     pub fn get_computer_move(
         &self,
@@ -48,13 +244,90 @@ impl ConnectFour {
     ) -> Result<usize, JsValue> {
         let state: GameState = from_value(state_js.clone())
             .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
-        let quality = match quality_str {
-            "bad" => Quality::Bad,
-            "medium" => Quality::Medium,
-            "best" => Quality::Best,
-            _ => Quality::Best,
-        };
-        Ok(self.core.get_computer_move(&state, quality))
+        self.check_drops()?;
+        Ok(self
+            .core
+            .get_computer_move(&state, parse_quality(quality_str))
+            .column())
+    }
+
+    /// Selects the built-in strategy used by `choose_move`.
+    #[wasm_bindgen]
+    pub fn set_quality(&mut self, quality_str: &str) {
+        self.set_strategy(parse_quality(quality_str).strategy());
+    }
+
+    /// Uses Monte Carlo tree search for `choose_move`, with the given number of
+    /// playouts per move and exploration constant.
+    #[wasm_bindgen]
+    pub fn set_mcts(&mut self, playouts: u32, exploration: f64) {
+        self.set_strategy(Box::new(MctsStrategy::new(playouts as u64, exploration)));
+    }
+
+    /// Picks a move with Monte Carlo tree search, running playouts until the time
+    /// budget in milliseconds runs out.
+    #[wasm_bindgen]
+    pub fn get_mcts_move_timed(
+        &self,
+        state_js: &JsValue,
+        budget_ms: f64,
+    ) -> Result<usize, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        self.check_drops()?;
+        let limits = SearchLimits::time(Duration::from_secs_f64(budget_ms.max(0.0) / 1000.0));
+        Ok(MctsStrategy::default().choose_move_with_limits(&self.core, &state, limits, &JsClock))
+    }
+
+    /// Asks the current strategy for a move and returns the column index.
+    /// Throws if the game is over.
+    #[wasm_bindgen]
+    pub fn choose_move(&self, state_js: &JsValue) -> Result<usize, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        self.check_drops()?;
+        if state.is_game_over {
+            return Err(JsValue::from_str("Invalid state: the game is already over"));
+        }
+        Ok(self.strategy.borrow_mut().choose_move(&self.core, &state))
+    }
+
+    /// Determines the computer's best move within a time budget in milliseconds.
+    #[wasm_bindgen]
+    pub fn get_computer_move_timed(
+        &self,
+        state_js: &JsValue,
+        budget_ms: f64,
+    ) -> Result<usize, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        self.check_drops()?;
+        let limits = SearchLimits::time(Duration::from_secs_f64(budget_ms.max(0.0) / 1000.0));
+        Ok(self
+            .core
+            .get_computer_move_with_limits(&state, limits, &JsClock)
+            .column())
+    }
+
+    /// Scores every legal column within a time budget in milliseconds and returns
+    /// an array of `{ column, score, outcome, principal_variation }` objects.
+    #[wasm_bindgen]
+    pub fn analyze(&self, state_js: &JsValue, budget_ms: f64) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        let limits = SearchLimits::time(Duration::from_secs_f64(budget_ms.max(0.0) / 1000.0));
+        let analysis = self.core.analyze_with_clock(&state, limits, &JsClock);
+        to_value(&analysis).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Returns the immediate wins, forced blocks, double threats and threat parity
+    /// of the position.
+    #[wasm_bindgen]
+    pub fn get_tactics(&self, state_js: &JsValue) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        to_value(&self.core.tactics(&state))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
     /// Checks if the game is over.
@@ -65,7 +338,8 @@ impl ConnectFour {
         Ok(state.is_game_over)
     }
 
-    /// Returns the winner as a string ("red" or "yellow"), or null if there's no winner.
+    /// Returns the winner as a string ("red", "yellow", "green" or "blue"), or null if
+    /// there's no winner.
     #[wasm_bindgen]
     pub fn get_winner(&self, state_js: &JsValue) -> Result<Option<String>, JsValue> {
         let state: GameState = from_value(state_js.clone())
@@ -73,12 +347,32 @@ impl ConnectFour {
         let winner = match state.winner {
             Some(Color::Red) => Some("red".to_string()),
             Some(Color::Yellow) => Some("yellow".to_string()),
+            Some(Color::Green) => Some("green".to_string()),
+            Some(Color::Blue) => Some("blue".to_string()),
             None => None,
         };
         Ok(winner)
     }
 
-    /// Returns the current player's color as a string ("red" or "yellow").
+    /// Returns the lines that decided the game as arrays of `[column, row]` pairs,
+    /// with row 0 at the top, or an empty array if nobody has won. In misère these
+    /// are the loser's lines.
+    #[wasm_bindgen]
+    pub fn get_winning_lines(&self, state_js: &JsValue) -> Result<JsValue, JsValue> {
+        let state: GameState = from_value(state_js.clone())
+            .map_err(|e| JsValue::from_str(&format!("Invalid state: {}", e)))?;
+        let lines = match state.winner {
+            Some(winner) if self.core.variant() == Variant::Misere => {
+                self.core.find_winning_lines(&state.board, winner.next(2))
+            }
+            Some(winner) => self.core.find_winning_lines(&state.board, winner),
+            None => Vec::new(),
+        };
+        to_value(&lines).map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Returns the current player's color as a string ("red", "yellow", "green" or
+    /// "blue").
     #[wasm_bindgen]
     pub fn get_current_player(&self, state_js: &JsValue) -> Result<String, JsValue> {
         let state: GameState = from_value(state_js.clone())
@@ -86,6 +380,8 @@ impl ConnectFour {
         let current_player = match state.current_player {
             Color::Red => "red".to_string(),
             Color::Yellow => "yellow".to_string(),
+            Color::Green => "green".to_string(),
+            Color::Blue => "blue".to_string(),
         };
         Ok(current_player)
     }